@@ -0,0 +1,414 @@
+use std::path::{Path, PathBuf};
+
+use parse_markdown::{highlight_dir, highlight_files, task_state, toc_tree, FrontMatter, MarkdownError, PageIndex, ParseOptions, TocNode};
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, LitStr, Token,
+};
+
+/// The arguments of the macros: a string, then optionally the [`ParseOptions`]
+struct MacroInput {
+    source: LitStr,
+    options: TokenStream2,
+}
+
+impl Parse for MacroInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let source = input.parse()?;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+        let options: TokenStream2 = input.parse()?;
+        // Check the options here, where errors can point to the macro call
+        syn::parse2::<ParseOptions>(options.clone())?;
+        Ok(Self { source, options })
+    }
+}
+
+/// Render a markdown page as an `Element`.
+///
+/// The path is relative to the `CARGO_MANIFEST_DIR` of the crate using the macro:
+///
+/// ```rust,ignore
+/// fn Home() -> Element {
+///     md_page!("src/pages/hello.md")
+/// }
+/// ```
+///
+/// The crate is rebuilt when the markdown file changes, or one of the files of the `highlight_dir`.
+///
+/// Markdown elements can be rendered by your own components, with their attributes as props:
+///
+/// ```rust,ignore
+/// md_page!("src/pages/hello.md", link => Link { to: {href} }, blockquote => Callout {})
+/// ```
+///
+/// In an app using dioxus-router, links to other pages can use the router instead of reloading the app:
+///
+/// ```rust,ignore
+/// md_page!("src/pages/hello.md", router: true, routes: { "/perfect_clear" => Route::PerfectClearPage {} })
+/// ```
+///
+/// With `check_links: true`, links to pages or headings that don't exist (`[setup](guide.md#setup)`)
+/// are compile errors. The linked pages are found relative to the page, and tracked like the page itself.
+///
+/// Task list checkboxes can't be changed, unless `interactive_tasks: true` is given. Their state is then
+/// kept in a signal of the component, and `on_toggle` can give an `EventHandler<TaskToggle>` to call
+/// when one of them changes:
+///
+/// ```rust,ignore
+/// #[component]
+/// fn Todo(on_toggle: EventHandler<TaskToggle>) -> Element {
+///     md_page!("src/pages/todo.md", on_toggle: on_toggle)
+/// }
+/// ```
+///
+/// With `highlight_classes: true`, code blocks are highlighted with classes instead of inline styles,
+/// for the stylesheet written by `parse_markdown::write_highlight_css`. `highlight_theme: "InspiredGitHub"`
+/// chooses the theme of the page, among syntect's themes and those of the `highlight_dir` set in
+/// the `[package.metadata.markdown]` of `Cargo.toml`, which can also have syntaxes for more languages.
+///
+/// Set `MARKDOWN_LENIENT=1` to replace broken `{{ }}` blocks with a placeholder instead of failing.
+/// Broken links are then only reported as warnings.
+#[proc_macro]
+pub fn md_page(input: TokenStream) -> TokenStream {
+    let MacroInput { source: file, options } = parse_macro_input!(input as MacroInput);
+    let (full_path, content) = match read_page(&file) {
+        Ok(page) => page,
+        Err(error) => return error.into(),
+    };
+
+    let (body, diagnostics) = match parse_located(&content, &options, Some((&full_path, &file.value()))) {
+        Ok(page) => page,
+        Err(errors) => {
+            let empty = quote!(dioxus_core::VNode::empty());
+            return compile_errors(&file, &full_path, errors, empty).into();
+        }
+    };
+    let diagnostics = diagnostics.into_iter().map(|diagnostic| diagnostic.with_file(&full_path));
+    let warnings = warnings(file.span(), diagnostics);
+
+    let tracking = track_files([full_path]);
+    quote! {
+        {
+            #tracking
+            #warnings
+            #body
+        }
+    }
+    .into()
+}
+
+/// The front matter of a markdown page, as a `dioxus_markdown::PageMeta`.
+///
+/// ```rust,ignore
+/// pub const META: PageMeta = md_meta!("src/pages/hello.md");
+/// ```
+///
+/// The page starts with YAML front matter between `---` lines, or TOML front matter between `+++` lines:
+///
+/// ```md
+/// ---
+/// title: Hello world
+/// description: The first page
+/// date: 2024-11-02
+/// tags: [dioxus, markdown]
+/// ---
+/// ```
+///
+/// The keys other than `title`, `description`, `date`, `tags` and `layout` are in `PageMeta::extra`.
+#[proc_macro]
+pub fn md_meta(input: TokenStream) -> TokenStream {
+    let file = parse_macro_input!(input as LitStr);
+    let (full_path, content) = match read_page(&file) {
+        Ok(page) => page,
+        Err(error) => return error.into(),
+    };
+
+    let meta = match parse_markdown::parse_front_matter(&content) {
+        Ok(meta) => meta,
+        Err(errors) => {
+            let empty = page_meta(FrontMatter::default());
+            return compile_errors(&file, &full_path, errors, empty).into();
+        }
+    };
+
+    let meta = page_meta(meta);
+    let tracking = track_files([full_path]);
+    quote! {
+        {
+            #tracking
+            #meta
+        }
+    }
+    .into()
+}
+
+/// The `PageMeta` expression of the front matter of a page
+fn page_meta(meta: FrontMatter) -> TokenStream2 {
+    let FrontMatter { title, description, date, tags, layout, extra } = meta;
+    let [title, description, date, layout] =
+        [title, description, date, layout].map(|value| match value {
+            Some(value) => quote!(Some(#value)),
+            None => quote!(None),
+        });
+    let extra = extra.iter().map(|(key, value)| quote!((#key, #value)));
+    quote! {
+        ::dioxus_markdown::PageMeta {
+            title: #title,
+            description: #description,
+            date: #date,
+            tags: &[#(#tags),*],
+            layout: #layout,
+            extra: &[#(#extra),*],
+        }
+    }
+}
+
+/// The headings of a markdown page, nested by level, as a `&[dioxus_markdown::TocEntry]`.
+///
+/// ```rust,ignore
+/// pub static TOC: &[TocEntry] = md_toc!("src/pages/hello.md");
+/// ```
+///
+/// Inside the page, a paragraph containing only `[[toc]]` is replaced by a list of links to the headings.
+#[proc_macro]
+pub fn md_toc(input: TokenStream) -> TokenStream {
+    let file = parse_macro_input!(input as LitStr);
+    let (full_path, content) = match read_page(&file) {
+        Ok(page) => page,
+        Err(error) => return error.into(),
+    };
+
+    // The errors of the page are reported by `md_page!`
+    let options = ParseOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let headings = parse_markdown::parse_page(&content, &options).map(|page| page.headings);
+    let toc = toc_entries(&toc_tree(&headings.unwrap_or_default()));
+
+    let tracking = track_files([full_path]);
+    quote! {
+        {
+            #tracking
+            #toc
+        }
+    }
+    .into()
+}
+
+/// The `&[TocEntry]` expression of the headings of a page
+fn toc_entries(nodes: &[TocNode]) -> TokenStream2 {
+    let entries = nodes.iter().map(|node| {
+        let heading = &node.heading;
+        let (level, text, anchor) = (heading.level, &heading.text, &heading.anchor);
+        let children = toc_entries(&node.children);
+        quote! {
+            ::dioxus_markdown::TocEntry {
+                level: #level,
+                text: #text,
+                anchor: #anchor,
+                children: #children,
+            }
+        }
+    });
+    quote!(&[#(#entries),*])
+}
+
+/// Render a markdown string literal as an `Element`.
+///
+/// ```rust,ignore
+/// fn Note() -> Element {
+///     md! { r#"
+///         **Note:** this is {{ Greet { name: "markdown" } }}
+///     "# }
+/// }
+/// ```
+///
+/// The markdown is unindented first, so it can follow the indentation of the code around it.
+/// Options can be given like for [`md_page!`].
+#[proc_macro]
+pub fn md(input: TokenStream) -> TokenStream {
+    let MacroInput { source, options } = parse_macro_input!(input as MacroInput);
+    let content = unindent(&source.value());
+
+    match parse_located(&content, &options, None) {
+        Ok((body, diagnostics)) => {
+            let warnings = warnings(source.span(), diagnostics);
+            quote!({ #warnings #body }).into()
+        }
+        Err(errors) => {
+            let errors = errors.into_iter().map(|error| {
+                let span = literal_subspan(&source, &error).unwrap_or(source.span());
+                let message = error.to_string();
+                let message = message.strip_prefix("error: ").unwrap_or(&message);
+                quote_spanned!(span=> compile_error!(#message);)
+            });
+            quote! {
+                {
+                    #(#errors)*
+                    dioxus_core::VNode::empty()
+                }
+            }
+            .into()
+        }
+    }
+}
+
+/// Read a page, from its path relative to the `CARGO_MANIFEST_DIR` of the crate using the macro
+fn read_page(file: &LitStr) -> Result<(PathBuf, String), TokenStream2> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let full_path = PathBuf::from(manifest_dir).join(file.value());
+
+    match std::fs::read_to_string(&full_path) {
+        Ok(content) => Ok((full_path, content)),
+        Err(e) => {
+            let message = format!("unable to read {}: {}", full_path.display(), e);
+            Err(quote_spanned!(file.span()=> compile_error!(#message)))
+        }
+    }
+}
+
+fn parse_options(options: &TokenStream2) -> ParseOptions {
+    let mut options: ParseOptions = syn::parse2(options.clone()).expect("options were checked by the macro");
+    options.lenient |= std::env::var_os("MARKDOWN_LENIENT").is_some();
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    options.highlight.dir = highlight_dir(Path::new(&manifest_dir));
+    // Set for crates with a build script, which may have compiled the syntaxes there
+    options.highlight.dump_dir = std::env::var_os("OUT_DIR").map(PathBuf::from);
+    options
+}
+
+/// The indentation common to every non-blank line, in bytes
+fn common_indent(text: &str) -> usize {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0)
+}
+
+fn unindent(text: &str) -> String {
+    let indent = common_indent(text);
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The span of the error inside the string literal.
+///
+/// This only works when the compiler supports `Literal::subspan` (nightly for now),
+/// and when the literal has no escapes, so that offsets in the markdown match the source code.
+fn literal_subspan(source: &LitStr, error: &MarkdownError) -> Option<Span> {
+    let token = source.token().to_string();
+    let prefix = token.find('"')? + 1;
+    if !token.starts_with('r') && token.contains('\\') {
+        return None;
+    }
+
+    let value = source.value();
+    // unindent removed the same amount of bytes at the start of every line
+    let indent = common_indent(&value);
+    let line_offset: usize = value
+        .split_inclusive('\n')
+        .take(error.line - 1)
+        .map(str::len)
+        .sum();
+    let column: usize = error
+        .snippet
+        .chars()
+        .take(error.column - 1)
+        .map(char::len_utf8)
+        .sum();
+    let start = prefix + line_offset + indent + column;
+    source.token().subspan(start..start + 1)
+}
+
+/// Parse the page, with the errors located in the markdown.
+///
+/// Inside a proc macro, every span of a parsed string points to the macro call site:
+/// the errors of rust code point to the start of its `{{ }}` block or `inject-dioxus` fence.
+///
+/// The `file` of a page is its full path and its path as written in the macro.
+/// Its links are checked if the `check_links` option is set.
+fn parse_located(
+    content: &str,
+    options: &TokenStream2,
+    file: Option<(&Path, &str)>,
+) -> Result<(TokenStream2, Vec<MarkdownError>), Vec<MarkdownError>> {
+    let mut options = parse_options(options);
+    if let Some((_, page)) = file {
+        options.tasks.page = page.to_string();
+    }
+    let mut page = parse_markdown::parse_page(content, &options)?;
+
+    // The syntaxes and themes of the project, and the pages the checked links point to
+    let mut tracked = options.highlight.dir.as_deref().map(highlight_files).unwrap_or_default();
+    if let (Some((file, _)), true) = (file, options.links.check) {
+        let mut index = PageIndex::default();
+        let broken_links = index.check(file, content, &page);
+        tracked.extend(index.read_pages().iter().cloned());
+        if options.lenient {
+            page.diagnostics.extend(broken_links);
+        } else if !broken_links.is_empty() {
+            return Err(broken_links);
+        }
+    }
+
+    let state = task_state(&options.tasks, &page.tasks);
+    let body = &page.body;
+    let tracking = track_files(tracked);
+    let body = quote!({ #tracking #state #body });
+    Ok((body, page.diagnostics))
+}
+
+/// Include the bytes of `files` in the crate, so that cargo rebuilds it when one of them changes
+fn track_files(files: impl IntoIterator<Item = PathBuf>) -> TokenStream2 {
+    let files = files.into_iter().map(|file| file.display().to_string());
+    quote!(#(const _: &[u8] = include_bytes!(#files);)*)
+}
+
+/// The diagnostics of a lenient page, as compiler warnings at `span`.
+///
+/// Proc macros can't emit warnings on stable rust: each diagnostic is the note of a deprecated item, used right away.
+fn warnings(span: Span, diagnostics: impl IntoIterator<Item = MarkdownError>) -> TokenStream2 {
+    let warnings = diagnostics.into_iter().map(|diagnostic| {
+        let message = diagnostic.to_string();
+        // The compiler prints `warning: ` in front of the note
+        let message = message.strip_prefix("error: ").unwrap_or(&message);
+        quote_spanned! {span=>
+            {
+                #[deprecated(note = #message)]
+                struct MarkdownWarning;
+                let _ = MarkdownWarning;
+            }
+        }
+    });
+    quote!(#(#warnings)*)
+}
+
+/// The errors of a page, followed by `fallback` so that the macro still has a value
+fn compile_errors(
+    file: &LitStr,
+    path: &Path,
+    errors: Vec<MarkdownError>,
+    fallback: TokenStream2,
+) -> TokenStream2 {
+    let errors = errors.into_iter().map(|error| {
+        let message = error.with_file(path).to_string();
+        // rustc already prints `error: ` in front of the message
+        let message = message.strip_prefix("error: ").unwrap_or(&message);
+        quote_spanned!(file.span()=> compile_error!(#message);)
+    });
+    quote! {
+        {
+            #(#errors)*
+            #fallback
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Markdown pages for dioxus: the macros, and the types of the data they generate.
+//!
+//! ```rust,ignore
+//! use dioxus_markdown::{md_meta, md_page, md_toc, PageMeta, TocEntry};
+//!
+//! pub const META: PageMeta = md_meta!("src/pages/hello.md");
+//! pub static TOC: &[TocEntry] = md_toc!("src/pages/hello.md");
+//!
+//! fn Hello() -> Element {
+//!     md_page!("src/pages/hello.md")
+//! }
+//! ```
+
+pub use dioxus_markdown_macro::{md, md_meta, md_page, md_toc};
+
+/// The front matter of a page, generated by [`md_meta!`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageMeta {
+    pub title: Option<&'static str>,
+    pub description: Option<&'static str>,
+    /// The date, as written in the front matter
+    pub date: Option<&'static str>,
+    pub tags: &'static [&'static str],
+    pub layout: Option<&'static str>,
+    /// Every other key, with its value as written. The items of a list are joined with `, `
+    pub extra: &'static [(&'static str, &'static str)],
+}
+
+impl PageMeta {
+    /// The value of a key of the front matter that is not a field of `PageMeta`
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        self.extra
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// A heading of a page, generated by [`md_toc!`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TocEntry {
+    /// 1 to 6
+    pub level: u8,
+    /// The text of the heading, without its formatting
+    pub text: &'static str,
+    /// The `id` of the heading, to link to it with `#anchor`
+    pub anchor: &'static str,
+    /// The headings of the section started by this heading
+    pub children: &'static [TocEntry],
+}
+
+/// A task of a task list that was checked or unchecked, given to the `on_toggle` handler of `md_page!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskToggle {
+    /// The path of the page, as written in `md_page!`. Empty for `md!`
+    pub page: &'static str,
+    /// The number of the task in the page, starting at 0
+    pub index: usize,
+    pub checked: bool,
+}
@@ -0,0 +1,56 @@
+//! The cost of parsing a page full of code blocks.
+//!
+//! The first page pays for loading the syntaxes and themes, the following pages reuse them.
+//!
+//! ```sh
+//! cargo bench -p parse-markdown --bench highlight
+//! ```
+
+use std::time::{Duration, Instant};
+
+use parse_markdown::{parse_page, ParseOptions};
+
+const BLOCKS: &[(&str, &str)] = &[
+    ("rust", "fn main() {\n    let name = \"dioxus\";\n    println!(\"Hello {name}\");\n}\n"),
+    ("toml", "[dependencies]\ndioxus = { version = \"0.6\", features = [\"web\"] }\n"),
+    ("sh", "cargo run -p parse-markdown --features hot-reload --bin md-hot-reload -- --port 8090\n"),
+    ("json", "{ \"name\": \"dioxus\", \"tags\": [\"rust\", \"ui\"] }\n"),
+    ("tsx", "export const App = () => <div className=\"app\">{items.map(item => <Item {...item} />)}</div>;\n"),
+];
+
+/// A page with `count` code blocks, with some text between them
+fn page(count: usize) -> String {
+    let mut page = String::from("# Benchmark\n\n");
+    for (lang, code) in BLOCKS.iter().cycle().take(count) {
+        page += &format!("Some **text** about `{lang}`.\n\n```{lang}\n{code}```\n\n");
+    }
+    page
+}
+
+fn time(options: &ParseOptions, page: &str, runs: u32) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        parse_page(page, options).unwrap();
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    let options = ParseOptions::default();
+    let classes = ParseOptions {
+        highlight: parse_markdown::HighlightOptions {
+            classes: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let first = time(&options, &page(1), 1);
+    println!("first page, loading the syntaxes: {first:?}");
+    for count in [1, 10, 100] {
+        let page = page(count);
+        let styles = time(&options, &page, 20);
+        let classes = time(&classes, &page, 20);
+        println!("page with {count:>3} code blocks: {styles:?} with inline styles, {classes:?} with classes");
+    }
+}
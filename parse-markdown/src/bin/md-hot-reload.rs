@@ -0,0 +1,155 @@
+//! Watch the markdown pages of a crate, and send their new templates to the running app.
+//!
+//! This is a devserver of its own, for desktop apps started with `cargo run`. It doesn't
+//! connect to the devserver of `dx serve`, so web apps don't receive its templates.
+//!
+//! Run it from the workspace root:
+//!
+//! ```sh
+//! cargo run -p parse-markdown --features hot-reload --bin md-hot-reload -- --port 8090
+//! ```
+//!
+//! It speaks the protocol of the dioxus devserver on `ws://127.0.0.1:<port>/_dioxus`: start the
+//! app with `DIOXUS_DEVSERVER_IP=127.0.0.1 DIOXUS_DEVSERVER_PORT=<port>` to connect it.
+
+use std::collections::HashMap;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use parse_markdown::hot_reload::{diff_pages, find_page_calls, PageCall, PageChange};
+use parse_markdown::highlight_dir;
+use tungstenite::{Message, WebSocket};
+
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+/// A markdown page rendered by some `md_page!` calls
+struct WatchedPage {
+    /// The source the app was compiled with
+    compiled: String,
+    calls: Vec<PageCall>,
+}
+
+fn main() {
+    let port = std::env::args()
+        .skip_while(|arg| arg != "--port")
+        .nth(1)
+        .map_or(8090, |port| port.parse().expect("invalid port"));
+
+    let clients: Clients = Arc::default();
+    let listener = TcpListener::bind(("127.0.0.1", port)).expect("unable to start the server");
+    let accepted = clients.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Ok(socket) = tungstenite::accept(stream) {
+                accepted.lock().unwrap().push(socket);
+            }
+        }
+    });
+    println!("Serving markdown hot reload on ws://127.0.0.1:{port}/_dioxus");
+
+    let mut pages = find_pages(Path::new("."));
+    loop {
+        std::thread::sleep(Duration::from_millis(250));
+
+        let mut templates = vec![];
+        let mut rebuild = false;
+        for (path, page) in &pages {
+            let Ok(current) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            for call in &page.calls {
+                match diff_pages(&page.compiled, &current, &call.options, &call.location) {
+                    PageChange::Unchanged => {}
+                    PageChange::Template(template) => templates.push(*template),
+                    PageChange::Rebuild => rebuild = true,
+                }
+            }
+            if rebuild {
+                println!("{} changed rust code: rebuild the app to see the changes", path.display());
+                break;
+            }
+        }
+
+        if rebuild {
+            // The app is going to be compiled with the current sources
+            pages = find_pages(Path::new("."));
+        } else if !templates.is_empty() {
+            let message = serde_json::json!({
+                "HotReload": {
+                    "templates": templates,
+                    "assets": [],
+                    "unknown_files": [],
+                }
+            });
+            let message = Message::Text(message.to_string());
+            clients
+                .lock()
+                .unwrap()
+                .retain_mut(|client| client.send(message.clone()).is_ok());
+        }
+    }
+}
+
+/// Find the pages rendered by the `md_page!` calls of the crates under `root`.
+fn find_pages(root: &Path) -> HashMap<PathBuf, WatchedPage> {
+    let mut pages: HashMap<PathBuf, WatchedPage> = HashMap::new();
+    for file in rust_files(root) {
+        let Ok(source) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        // `file!()` is relative to the workspace root, with `/` separators
+        let relative = file.strip_prefix(root).unwrap_or(&file);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let manifest_dir = manifest_dir(&file);
+
+        for mut call in find_page_calls(&relative, &source) {
+            // The options `md_page!` completes from its environment
+            call.options.lenient |= std::env::var_os("MARKDOWN_LENIENT").is_some();
+            call.options.highlight.dir = highlight_dir(&manifest_dir);
+            call.options.tasks.page = call.page.clone();
+
+            let page = manifest_dir.join(&call.page);
+            let Ok(compiled) = std::fs::read_to_string(&page) else {
+                continue;
+            };
+            pages
+                .entry(page)
+                .or_insert(WatchedPage { compiled, calls: vec![] })
+                .calls
+                .push(call);
+        }
+    }
+    pages
+}
+
+fn rust_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name == "target" {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(rust_files(&path));
+        } else if name.ends_with(".rs") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// The directory of the `Cargo.toml` of the crate containing `file`
+fn manifest_dir(file: &Path) -> PathBuf {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").exists())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
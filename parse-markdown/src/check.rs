@@ -0,0 +1,168 @@
+//! Find links to markdown pages or headings that don't exist.
+//!
+//! Links between pages are written with the path of the markdown file: `[setup](guide.md#setup)`.
+//! The index knows the anchors of the headings of every page, so that both the page and
+//! the fragment of each link can be checked at compile time.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::links::{is_external, split_suffix};
+use crate::{parse_page, MarkdownError, Page, ParseOptions};
+
+/// The anchors of the headings of markdown pages, by path.
+///
+/// Pages that are not in the index yet are read from the disk when a link points to them.
+#[derive(Debug, Default)]
+pub struct PageIndex {
+    /// `None` for the pages that could not be read
+    pages: HashMap<PathBuf, Option<Vec<String>>>,
+    /// The pages read from the disk, in order
+    read: Vec<PathBuf>,
+}
+
+impl PageIndex {
+    /// Add a page to the index, without reading it from the disk.
+    pub fn insert(&mut self, path: impl AsRef<Path>, page: &Page) {
+        self.pages
+            .insert(normalize(path.as_ref()), Some(anchors(page)));
+    }
+
+    /// Check the links of the page at `path` to other pages (`other.md`, `../guide/index.md#setup`)
+    /// and to its own headings (`#setup`).
+    ///
+    /// Links to other sites or to paths of the app that are not markdown files are ignored.
+    pub fn check(&mut self, path: impl AsRef<Path>, content: &str, page: &Page) -> Vec<MarkdownError> {
+        let path = normalize(path.as_ref());
+        self.insert(&path, page);
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let mut errors = vec![];
+        for link in &page.links {
+            let Some((file, fragment)) = internal_link(&link.dest) else {
+                continue;
+            };
+            let target = if file.is_empty() {
+                path.clone()
+            } else {
+                normalize(&dir.join(file))
+            };
+            let message = match (self.anchors(&target), fragment) {
+                (None, _) => format!("broken link: `{file}` does not exist"),
+                (Some(anchors), Some(fragment)) if !anchors.iter().any(|a| a == fragment) => {
+                    if file.is_empty() {
+                        format!("broken link: no heading `#{fragment}` in this page")
+                    } else {
+                        format!("broken link: no heading `#{fragment}` in `{file}`")
+                    }
+                }
+                _ => continue,
+            };
+            errors.push(MarkdownError::new(content, link.offset, message).with_file(&path));
+        }
+        errors
+    }
+
+    /// The pages read from the disk to check the links, which the macros track.
+    pub fn read_pages(&self) -> &[PathBuf] {
+        &self.read
+    }
+
+    fn anchors(&mut self, path: &Path) -> Option<&Vec<String>> {
+        let read = &mut self.read;
+        self.pages
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let content = std::fs::read_to_string(path).ok()?;
+                read.push(path.to_path_buf());
+                let page = parse_page(&content, &lenient()).ok()?;
+                Some(anchors(&page))
+            })
+            .as_ref()
+    }
+}
+
+fn anchors(page: &Page) -> Vec<String> {
+    page.headings.iter().map(|heading| heading.anchor.clone()).collect()
+}
+
+/// Check the links of every markdown page under `dir`, from a build script.
+///
+/// ```rust,ignore
+/// for error in parse_markdown::check_links("src/pages") {
+///     println!("cargo:warning={}", error.to_string().replace('\n', " "));
+/// }
+/// ```
+pub fn check_links(dir: impl AsRef<Path>) -> Vec<MarkdownError> {
+    let mut index = PageIndex::default();
+    let mut errors = vec![];
+    for path in markdown_files(dir.as_ref()) {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(page) = parse_page(&content, &lenient()) else {
+            continue;
+        };
+        errors.extend(index.check(&path, &content, &page));
+    }
+    errors
+}
+
+/// Only the anchors of the pages matter: don't stop at broken `{{ }}` blocks
+fn lenient() -> ParseOptions {
+    ParseOptions {
+        lenient: true,
+        ..Default::default()
+    }
+}
+
+/// The markdown file and the fragment of a link between pages, or `None` for any other link.
+/// The file is empty for a link to a heading of the same page.
+fn internal_link(dest: &str) -> Option<(&str, Option<&str>)> {
+    if is_external(dest) || dest.contains("{=") {
+        return None;
+    }
+    let (file, suffix) = split_suffix(dest);
+    let fragment = suffix
+        .split_once('#')
+        .map(|(_, fragment)| fragment)
+        .filter(|fragment| !fragment.is_empty());
+    let is_page = file.ends_with(".md") && !file.starts_with('/');
+    match (file.is_empty(), is_page) {
+        (true, _) if fragment.is_some() => Some((file, fragment)),
+        (false, true) => Some((file, fragment)),
+        _ => None,
+    }
+}
+
+/// Resolve `.` and `..` without touching the disk, since the path may not exist.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn markdown_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(markdown_files(&path));
+        } else if path.extension().is_some_and(|extension| extension == "md") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
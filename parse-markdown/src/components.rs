@@ -0,0 +1,169 @@
+//! Render markdown elements with your own components.
+//!
+//! An override maps a markdown element to a component call, like `link => Link { to: {href} }`.
+//! `{href}` is replaced by the value of the `href` attribute of the link, and the content
+//! of the link becomes the children of the component.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use dioxus_rsx::{BodyNode, Component};
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, Token,
+};
+
+/// A markdown element that can be rendered by a component, with the attributes it provides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarkdownElement {
+    /// `level` (1 to 6) and `id`
+    Heading,
+    Paragraph,
+    BlockQuote,
+    /// `code`, `lang` and `title`. The component has no children
+    CodeBlock,
+    /// Inline code: `code`. The component has no children
+    Code,
+    /// `href` and `title`
+    Link,
+    /// `src`, `alt` and `title`. The component has no children
+    Image,
+    /// `ordered` (a bool) and `start` (the first number of an ordered list)
+    List,
+    Item,
+    Table,
+    Emphasis,
+    Strong,
+    Strikethrough,
+    /// The component has no children
+    Rule,
+}
+
+impl MarkdownElement {
+    const ALL: [(&'static str, Self); 14] = [
+        ("heading", Self::Heading),
+        ("paragraph", Self::Paragraph),
+        ("blockquote", Self::BlockQuote),
+        ("code_block", Self::CodeBlock),
+        ("code", Self::Code),
+        ("link", Self::Link),
+        ("image", Self::Image),
+        ("list", Self::List),
+        ("item", Self::Item),
+        ("table", Self::Table),
+        ("emphasis", Self::Emphasis),
+        ("strong", Self::Strong),
+        ("strikethrough", Self::Strikethrough),
+        ("rule", Self::Rule),
+    ];
+
+    fn name(self) -> &'static str {
+        Self::ALL.iter().find(|(_, element)| *element == self).unwrap().0
+    }
+}
+
+impl fmt::Display for MarkdownElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Parse for MarkdownElement {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        Self::ALL
+            .iter()
+            .find(|(name, _)| ident == name)
+            .map(|(_, element)| *element)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|(name, _)| *name).collect();
+                syn::Error::new(
+                    ident.span(),
+                    format!("unknown markdown element `{ident}`, expected one of {}", names.join(", ")),
+                )
+            })
+    }
+}
+
+/// The components used to render markdown elements: `link => Link { to: {href} }, blockquote => Callout {}`
+#[derive(Debug, Clone, Default)]
+pub struct ComponentMap(HashMap<MarkdownElement, TokenStream>);
+
+impl ComponentMap {
+    /// Render `element` with `component`, a component call like `Link { to: {href} }`.
+    pub fn insert(&mut self, element: MarkdownElement, component: TokenStream) -> syn::Result<()> {
+        syn::parse2::<Component>(component.clone())?;
+        self.0.insert(element, component);
+        Ok(())
+    }
+
+    pub fn get(&self, element: MarkdownElement) -> Option<&TokenStream> {
+        self.0.get(&element)
+    }
+}
+
+impl Parse for ComponentMap {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut map = ComponentMap::default();
+        while !input.is_empty() {
+            let element: MarkdownElement = input.parse()?;
+            input.parse::<Token![=>]>()?;
+
+            map.insert(element, parse_component_call(input)?)?;
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(map)
+    }
+}
+
+/// The tokens of a component call: its path, up to and including its braces
+pub(crate) fn parse_component_call(input: ParseStream) -> syn::Result<TokenStream> {
+    let mut component = TokenStream::new();
+    loop {
+        let token: TokenTree = input.parse()?;
+        let is_body = matches!(&token, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace);
+        component.extend([token]);
+        if is_body {
+            return Ok(component);
+        }
+    }
+}
+
+/// Build the node rendering an element with a component.
+///
+/// Each `{name}` block in the component is replaced by the value of the attribute `name`.
+pub(crate) fn override_node(
+    component: &TokenStream,
+    attributes: &[(&str, TokenStream)],
+) -> syn::Result<BodyNode> {
+    let tokens = replace_attributes(component.clone(), attributes);
+    syn::parse2::<Component>(tokens).map(BodyNode::Component)
+}
+
+fn replace_attributes(tokens: TokenStream, attributes: &[(&str, TokenStream)]) -> TokenStream {
+    let mut output = TokenStream::new();
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                if let (Delimiter::Brace, [TokenTree::Ident(ident)]) = (group.delimiter(), &inner[..]) {
+                    if let Some((_, value)) = attributes.iter().find(|(name, _)| ident == name) {
+                        output.extend(value.clone());
+                        continue;
+                    }
+                }
+                let mut replaced =
+                    Group::new(group.delimiter(), replace_attributes(group.stream(), attributes));
+                replaced.set_span(group.span());
+                output.extend([TokenTree::Group(replaced)]);
+            }
+            token => output.extend([token]),
+        }
+    }
+    output
+}
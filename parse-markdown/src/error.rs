@@ -0,0 +1,98 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// An error found while turning a markdown page into rsx.
+///
+/// The location always points into the original `.md` source, even when the error
+/// comes from rust code inside a `{{ }}` block or an `inject-dioxus` code fence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownError {
+    pub message: String,
+    /// The markdown file the error comes from, if known
+    pub file: Option<PathBuf>,
+    /// 1-based line in the markdown source
+    pub line: usize,
+    /// 1-based column (in characters) in the markdown source
+    pub column: usize,
+    /// The full source line the error points to
+    pub snippet: String,
+}
+
+impl MarkdownError {
+    /// Build an error pointing at the byte `offset` of `source`.
+    pub(crate) fn new(source: &str, offset: usize, message: impl Into<String>) -> Self {
+        let offset = offset.min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..].find('\n').map_or(source.len(), |i| offset + i);
+
+        Self {
+            message: message.into(),
+            file: None,
+            line: source[..line_start].matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            snippet: source[line_start..line_end].trim_end_matches('\r').to_string(),
+        }
+    }
+
+    /// Map a syn error back into the markdown source.
+    ///
+    /// `offset` is the byte offset in `source` of the text that was given to syn.
+    /// Spans created from a string (with the `span-locations` feature of proc-macro2)
+    /// are relative to that string, so we only have to shift them.
+    pub(crate) fn from_syn(source: &str, offset: usize, error: &syn::Error) -> Self {
+        let start = error.span().start();
+        let offset = if start.line == 0 {
+            // call-site span: the best we know is where the parsed text starts
+            offset
+        } else {
+            offset + byte_offset(&source[offset..], start.line, start.column)
+        };
+        Self::new(source, offset, error.to_string())
+    }
+
+    /// Attach the path of the markdown file this error comes from.
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+/// Convert a line (1-based) and a column (0-based, in chars) into a byte offset in `text`.
+pub(crate) fn byte_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum();
+    let line_text = &text[line_start..];
+    line_start
+        + line_text
+            .char_indices()
+            .nth(column)
+            .map_or(line_text.len(), |(i, _)| i)
+}
+
+impl fmt::Display for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = self
+            .file
+            .as_ref()
+            .map_or("<markdown>".to_string(), |file| file.display().to_string());
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let marker_indent: String = self
+            .snippet
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{gutter}--> {file}:{}:{}", self.line, self.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line_number} | {}", self.snippet)?;
+        write!(f, "{gutter} | {marker_indent}^")
+    }
+}
+
+impl std::error::Error for MarkdownError {}
@@ -0,0 +1,116 @@
+//! Footnotes: `[^label]` references, and their `[^label]: ...` definitions.
+//!
+//! Footnotes are numbered in the order they are first referenced in the page, and their definitions
+//! are rendered together in a `section.footnotes` at the end of the page, wherever they are written.
+
+use dioxus_rsx::BodyNode;
+use syn::parse_quote;
+
+use crate::escape_text;
+
+/// A footnote of a page, referenced or defined
+#[derive(Debug)]
+pub(crate) struct Footnote {
+    label: String,
+    /// The number of the footnote, given by its first reference
+    number: Option<usize>,
+    /// How many times the footnote is referenced
+    references: usize,
+    /// The content of the definition, if it was found
+    definition: Option<Vec<BodyNode>>,
+}
+
+/// The footnote `label`, adding it to the footnotes of the page if it is new
+fn footnote<'a>(footnotes: &'a mut Vec<Footnote>, label: &str) -> &'a mut Footnote {
+    let index = match footnotes.iter().position(|footnote| footnote.label == label) {
+        Some(index) => index,
+        None => {
+            footnotes.push(Footnote {
+                label: label.to_string(),
+                number: None,
+                references: 0,
+                definition: None,
+            });
+            footnotes.len() - 1
+        }
+    };
+    &mut footnotes[index]
+}
+
+/// The superscript link of a reference to footnote `label`.
+/// The first reference of a footnote gives it the next number.
+pub(crate) fn footnote_reference(footnotes: &mut Vec<Footnote>, label: &str) -> BodyNode {
+    let next = footnotes.iter().filter(|footnote| footnote.number.is_some()).count() + 1;
+    let footnote = footnote(footnotes, label);
+    let number = *footnote.number.get_or_insert(next);
+    footnote.references += 1;
+    // Only the first reference is the target of the back-link
+    let id = match footnote.references {
+        1 => format!("fnref-{number}"),
+        count => format!("fnref-{number}-{count}"),
+    };
+    let id = escape_text(&id);
+    let href = escape_text(&format!("#fn-{number}"));
+    let text = number.to_string();
+    parse_quote! {
+        sup {
+            class: "footnote-reference",
+            id: #id,
+            a { href: #href, #text }
+        }
+    }
+}
+
+/// The element collecting the content of a definition, until [`set_definition`]
+pub(crate) fn footnote_item() -> BodyNode {
+    parse_quote! {
+        li {}
+    }
+}
+
+pub(crate) fn set_definition(footnotes: &mut Vec<Footnote>, label: &str, definition: BodyNode) {
+    if let BodyNode::Element(element) = definition {
+        footnote(footnotes, label).definition = Some(element.children);
+    }
+}
+
+/// The `section` with the definitions of the footnotes, or `None` if the page has none
+pub(crate) fn footnote_section(footnotes: &[Footnote]) -> Option<BodyNode> {
+    // Referenced footnotes in the order of their numbers, then the others in the order of the page
+    let mut ordered: Vec<&Footnote> = footnotes.iter().collect();
+    ordered.sort_by_key(|footnote| footnote.number.unwrap_or(usize::MAX));
+    let items: Vec<BodyNode> = ordered
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, footnote)| {
+            let definition = footnote.definition.clone()?;
+            let number = index + 1;
+            let id = escape_text(&format!("fn-{number}"));
+            let mut item: BodyNode = parse_quote!(li { id: #id });
+            if let BodyNode::Element(element) = &mut item {
+                element.children = definition;
+                if footnote.references > 0 {
+                    let href = escape_text(&format!("#fnref-{number}"));
+                    element.children.push(parse_quote! {
+                        a { href: #href, class: "footnote-backref", "↩" }
+                    });
+                }
+            }
+            Some(item)
+        })
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+    let mut list: BodyNode = parse_quote!(ol {});
+    if let BodyNode::Element(element) = &mut list {
+        element.children = items;
+    }
+    let mut section: BodyNode = parse_quote! {
+        section { class: "footnotes" }
+    };
+    if let BodyNode::Element(element) = &mut section {
+        element.children.push(list);
+    }
+    Some(section)
+}
@@ -0,0 +1,261 @@
+//! Metadata at the top of a page.
+//!
+//! YAML front matter is written between `---` lines, TOML front matter between `+++` lines:
+//!
+//! ```md
+//! ---
+//! title: Hello world
+//! tags: [dioxus, markdown]
+//! ---
+//! ```
+//!
+//! The keys of TOML tables are flattened: `draft` in `[extra]` is `extra.draft`.
+//!
+//! A block between `---` lines is only front matter when it is a list of `key: value`.
+//! Otherwise it is markdown, like a paragraph between two thematic breaks.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+use syn::__private::Span;
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
+
+use crate::error::byte_offset;
+use crate::{leading_whitespace, LocatedError};
+
+/// The metadata of a page, from its front matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// The date, as written
+    pub date: Option<String>,
+    pub tags: Vec<String>,
+    pub layout: Option<String>,
+    /// Every other key, with its value as written. The items of a list are joined with `, `
+    pub extra: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Yaml,
+    Toml,
+}
+
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(text) => f.write_str(text),
+            Value::List(items) => f.write_str(&items.join(", ")),
+        }
+    }
+}
+
+/// Parse the front matter of a page, and blank it out of the markdown.
+///
+/// The front matter is replaced with spaces rather than removed,
+/// so that byte offsets in the markdown are still offsets in the page.
+pub(crate) fn split_front_matter<'a>(
+    content: &'a str,
+    errors: &mut Vec<LocatedError>,
+) -> (FrontMatter, Cow<'a, str>) {
+    let Some((format, range, end)) = find_front_matter(content) else {
+        return (FrontMatter::default(), Cow::Borrowed(content));
+    };
+
+    let text = &content[range.clone()];
+    let entries = match format {
+        Format::Yaml => yaml_entries(text, range.start, errors),
+        Format::Toml => Some(toml_entries(text, range.start, errors)),
+    };
+    let Some(entries) = entries else {
+        return (FrontMatter::default(), Cow::Borrowed(content));
+    };
+    let meta = front_matter(entries, text, range.start, errors);
+
+    let blank: String = content[..end]
+        .bytes()
+        .map(|b| if b == b'\n' { '\n' } else { ' ' })
+        .collect();
+    (meta, Cow::Owned(blank + &content[end..]))
+}
+
+/// Find the front matter at the very start of the page: its format,
+/// the byte range of its content, and the end of its closing line.
+fn find_front_matter(content: &str) -> Option<(Format, Range<usize>, usize)> {
+    let (format, delimiter) = if content.starts_with("---") {
+        (Format::Yaml, "---")
+    } else if content.starts_with("+++") {
+        (Format::Toml, "+++")
+    } else {
+        return None;
+    };
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != delimiter {
+        return None;
+    }
+
+    let start = first_line_end + 1;
+    let mut line_start = start;
+    for line in content[start..].split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((format, start..line_start, line_start + line.len()));
+        }
+        line_start += line.len();
+    }
+    // Not closed: this is a thematic break, not front matter
+    None
+}
+
+fn front_matter(
+    entries: Vec<(String, Value)>,
+    text: &str,
+    offset: usize,
+    errors: &mut Vec<LocatedError>,
+) -> FrontMatter {
+    let mut meta = FrontMatter::default();
+    for (key, value) in entries {
+        let field = match key.as_str() {
+            "title" => &mut meta.title,
+            "description" => &mut meta.description,
+            "date" => &mut meta.date,
+            "layout" => &mut meta.layout,
+            "tags" => {
+                meta.tags = match value {
+                    Value::Text(tag) => vec![tag],
+                    Value::List(tags) => tags,
+                };
+                continue;
+            }
+            _ => {
+                meta.extra.push((key, value.to_string()));
+                continue;
+            }
+        };
+        match value {
+            Value::Text(value) => *field = Some(value),
+            Value::List(_) => errors.push((
+                offset + key_offset(text, &key),
+                error(format!("`{key}` must be a single value, not a list")),
+            )),
+        }
+    }
+    meta
+}
+
+/// The entries of YAML front matter, or `None` if it is not a mapping: the block is markdown then.
+/// Front matter with a syntax error is still front matter, so that the error is reported.
+fn yaml_entries(text: &str, offset: usize, errors: &mut Vec<LocatedError>) -> Option<Vec<(String, Value)>> {
+    let documents = match YamlLoader::load_from_str(text) {
+        Ok(documents) => documents,
+        Err(e) => {
+            let marker = e.marker();
+            let message = e.to_string();
+            // the location is already part of the error
+            let message = message.split(" at line ").next().unwrap_or_default();
+            errors.push((
+                offset + byte_offset(text, marker.line(), marker.col()),
+                error(format!("invalid front matter: {message}")),
+            ));
+            return Some(vec![]);
+        }
+    };
+    // Empty front matter, between two `---` lines
+    let Some(document) = documents.into_iter().next() else {
+        return Some(vec![]);
+    };
+    let Yaml::Hash(entries) = document else {
+        return None;
+    };
+
+    let entries = entries
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Yaml::Array(items) => Value::List(items.iter().map(yaml_text).collect()),
+                value => Value::Text(yaml_text(&value)),
+            };
+            (yaml_text(&key), value)
+        })
+        .collect();
+    Some(entries)
+}
+
+fn yaml_text(value: &Yaml) -> String {
+    match value {
+        Yaml::String(text) | Yaml::Real(text) => text.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Null => String::new(),
+        value => {
+            let mut output = String::new();
+            let _ = YamlEmitter::new(&mut output).dump(value);
+            output.trim_start_matches("---").trim().to_string()
+        }
+    }
+}
+
+fn toml_entries(text: &str, offset: usize, errors: &mut Vec<LocatedError>) -> Vec<(String, Value)> {
+    match text.parse::<toml::Table>() {
+        Ok(table) => {
+            let mut entries = vec![];
+            toml_table_entries(table, "", &mut entries);
+            entries
+        }
+        Err(e) => {
+            let position = e.span().map_or(0, |span| span.start);
+            errors.push((offset + position, error(format!("invalid front matter: {}", e.message()))));
+            vec![]
+        }
+    }
+}
+
+/// The entries of a TOML table, with the keys of its tables prefixed by their name
+fn toml_table_entries(table: toml::Table, prefix: &str, entries: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let key = format!("{prefix}{key}");
+        match value {
+            toml::Value::Table(table) => toml_table_entries(table, &format!("{key}."), entries),
+            toml::Value::Array(items) => entries.push((key, Value::List(items.iter().map(toml_value_text).collect()))),
+            value => entries.push((key, Value::Text(toml_value_text(&value)))),
+        }
+    }
+}
+
+/// A TOML value as text: strings without their quotes, other values as written in TOML
+fn toml_value_text(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// The value of `key` in a TOML file, like `application.asset_dir` in `Dioxus.toml`
+pub(crate) fn toml_text(text: &str, key: &str) -> Option<String> {
+    toml_entries(text, 0, &mut vec![])
+        .into_iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.to_string())
+}
+
+/// The byte offset of the line defining `key`, or 0 if it can't be found
+fn key_offset(text: &str, key: &str) -> usize {
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with(key) {
+            return line_start + leading_whitespace(line);
+        }
+        line_start += line.len();
+    }
+    0
+}
+
+fn error(message: impl fmt::Display) -> syn::Error {
+    syn::Error::new(Span::call_site(), message)
+}
@@ -0,0 +1,541 @@
+//! Syntax highlighting of code blocks, with syntect.
+//!
+//! The language of a block is the first word of its info string: ```` ```toml ```` or ```` ```rust,ignore ````.
+//! It is looked up among the syntaxes by name or file extension, then among the aliases below.
+//! syntect has no TOML syntax, so a small one is shipped with this crate.
+//! Code in an unknown language is rendered as plain text.
+//!
+//! By default, tokens are colored with inline styles from a theme. With classes, they are marked with
+//! the scopes of the syntax, like `hl-keyword`, and colored by the stylesheet of [`write_highlight_css`],
+//! which can change with the color scheme the reader prefers.
+//!
+//! A project can add its own `.sublime-syntax` and `.tmTheme` files, in the directory set by
+//! `highlight_dir` in the `[package.metadata.markdown]` of its `Cargo.toml`. They are loaded with syntect's defaults.
+//! A page can choose its theme, and a block can choose its own: ```` ```rust theme="InspiredGitHub" ````.
+//!
+//! Loading the syntaxes takes much longer than highlighting a block, so they are loaded once
+//! for all the pages of a crate. The syntaxes of a project can also be compiled by its build script,
+//! with [`dump_highlight_sets`].
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use syntect::dumps::{dump_to_uncompressed_file, from_uncompressed_dump_file};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, Theme, ThemeSet};
+use syntect::html::{
+    css_for_theme_with_class_style, highlighted_html_for_string, line_tokens_to_classed_spans,
+    start_highlighted_html_snippet, styled_line_to_highlighted_html, ClassStyle, ClassedHTMLGenerator,
+    IncludeBackground,
+};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxDefinition, SyntaxReference, SyntaxSet, SyntaxSetBuilder};
+use syntect::util::LinesWithEndings;
+
+use crate::front_matter::toml_text;
+
+/// Languages written in fences that syntect doesn't know, with the language highlighted in their place.
+/// They are only used when no syntax has the name of the language itself.
+const ALIASES: &[(&str, &str)] = &[
+    ("shell", "sh"),
+    ("console", "sh"),
+    ("shellsession", "sh"),
+    ("ts", "js"),
+    ("typescript", "js"),
+    ("tsx", "js"),
+    ("jsx", "js"),
+    ("mjs", "js"),
+    ("cjs", "js"),
+    ("jsonc", "json"),
+    ("json5", "json"),
+    ("golang", "go"),
+];
+
+/// TOML is common in the docs of rust projects, but syntect has no syntax for it
+const TOML_SYNTAX: &str = include_str!("../syntaxes/TOML.sublime-syntax");
+
+/// The theme of code blocks highlighted with inline styles
+const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// The classes of highlighted tokens are prefixed, so that they don't clash with the classes of the app
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// The style of each line of a block with `linenos` or `hl_lines`. The newline stays inside the line
+const LINE_STYLE: &str = "display: flex;";
+/// The style of line numbers: they are not selected with the code
+const LINENO_STYLE: &str = "user-select: none; margin-right: 1em; opacity: 0.6;";
+/// The background of highlighted lines, for themes without one
+const LINE_HIGHLIGHT: &str = "rgba(128, 128, 128, 0.25)";
+
+/// The files written by [`dump_highlight_sets`]
+const SYNTAX_DUMP: &str = "markdown-syntaxes.packdump";
+const THEME_DUMP: &str = "markdown-themes.themedump";
+
+/// How code blocks are highlighted.
+#[derive(Debug, Clone, Default)]
+pub struct HighlightOptions {
+    /// Mark tokens with classes instead of inline styles, to color them with the stylesheet
+    /// of [`write_highlight_css`]. Blocks choosing their own theme still use inline styles
+    pub classes: bool,
+    /// The theme of the code blocks of the page, `base16-ocean.dark` by default
+    pub theme: Option<String>,
+    /// The directory of the syntaxes and themes of the project, see [`highlight_dir`]
+    pub dir: Option<PathBuf>,
+    /// The directory where [`dump_highlight_sets`] compiled them, the `OUT_DIR` of the crate in the macros.
+    /// They are loaded from `dir` if it has no dump
+    pub dump_dir: Option<PathBuf>,
+}
+
+/// The themes of the stylesheet of code blocks highlighted with classes.
+///
+/// The names are those of syntect's themes: `InspiredGitHub`, `Solarized (dark)`, `Solarized (light)`,
+/// `base16-eighties.dark`, `base16-mocha.dark`, `base16-ocean.dark` and `base16-ocean.light`,
+/// or the file names of the themes of the project, without `.tmTheme`.
+#[derive(Debug, Clone)]
+pub struct HighlightThemes {
+    /// The theme used when the reader has no preferred color scheme
+    pub default: String,
+    /// The theme used when the reader prefers a light color scheme
+    pub light: Option<String>,
+    /// The theme used when the reader prefers a dark color scheme
+    pub dark: Option<String>,
+}
+
+impl Default for HighlightThemes {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_THEME.to_string(),
+            light: None,
+            dark: None,
+        }
+    }
+}
+
+/// The fence info string of a code block: its language, then attributes like
+/// `title="main.rs" linenos hl_lines="3-5" theme="InspiredGitHub"`. Attributes that are not known are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FenceInfo {
+    pub lang: Option<String>,
+    pub theme: Option<String>,
+    /// The file name shown above the code
+    pub title: Option<String>,
+    /// Show the number of each line
+    pub linenos: bool,
+    /// The ranges of highlighted lines, numbered from 1, with their ends included
+    pub hl_lines: Vec<(usize, usize)>,
+}
+
+pub(crate) fn fence_info(info: &str) -> Result<FenceInfo, String> {
+    let mut fence = FenceInfo::default();
+    let lang_end = info.find(|c: char| c.is_whitespace() || c == ',').unwrap_or(info.len());
+    let mut rest = info;
+    if lang_end > 0 && !info[..lang_end].contains('=') {
+        fence.lang = Some(info[..lang_end].to_string());
+        rest = &info[lang_end..];
+    }
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let name_end = rest.find(|c: char| c.is_whitespace() || c == ',' || c == '=').unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        rest = &rest[name_end..];
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let (value, after) = match after.strip_prefix('"') {
+                    Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+                    None => after.split_at(after.find(char::is_whitespace).unwrap_or(after.len())),
+                };
+                rest = after;
+                Some(value)
+            }
+            None => None,
+        };
+        match (name, value) {
+            ("theme", Some(theme)) => fence.theme = Some(theme.to_string()),
+            ("title", Some(title)) => fence.title = Some(title.to_string()),
+            ("linenos", None) => fence.linenos = true,
+            ("hl_lines", Some(lines)) => fence.hl_lines = line_ranges(lines)?,
+            _ => {}
+        }
+    }
+    Ok(fence)
+}
+
+/// Parse line ranges like `1 3-5` or `1,3-5`
+fn line_ranges(lines: &str) -> Result<Vec<(usize, usize)>, String> {
+    lines
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|range| !range.is_empty())
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            match (start.trim().parse(), end.trim().parse()) {
+                (Ok(start), Ok(end)) if 0 < start && start <= end => Ok((start, end)),
+                _ => Err(format!("invalid line range `{range}` in hl_lines, expected a line like `3` or lines like `3-5`")),
+            }
+        })
+        .collect()
+}
+
+/// The syntax highlighting code written in `lang`, with the set it belongs to.
+/// TOML is found in its own set, unless the project has its own TOML syntax.
+pub(crate) fn find_syntax<'a>(syntaxes: &'a SyntaxSet, lang: Option<&str>) -> (&'a SyntaxSet, &'a SyntaxReference) {
+    let Some(lang) = lang else {
+        return (syntaxes, syntaxes.find_syntax_plain_text());
+    };
+    let lang = lang.to_lowercase();
+    let syntax = syntaxes.find_syntax_by_token(&lang).or_else(|| {
+        let (_, alias) = ALIASES.iter().find(|(name, _)| *name == lang)?;
+        syntaxes.find_syntax_by_token(alias)
+    });
+    if let Some(syntax) = syntax {
+        return (syntaxes, syntax);
+    }
+    let toml = toml_syntaxes();
+    match toml.find_syntax_by_token(&lang) {
+        Some(syntax) => (toml, syntax),
+        None => (syntaxes, syntaxes.find_syntax_plain_text()),
+    }
+}
+
+/// The highlighted HTML of a code block, or an error if its syntaxes or its theme can't be found
+pub(crate) fn highlight(options: &HighlightOptions, code: &str, fence: &FenceInfo) -> Result<String, String> {
+    let sets = highlight_sets(options)?;
+    let (syntaxes, syntax) = find_syntax(&sets.syntaxes, fence.lang.as_deref());
+    // Lines are only wrapped in their own element when they need it
+    let by_line = fence.linenos || !fence.hl_lines.is_empty();
+    if options.classes && fence.theme.is_none() {
+        if !by_line {
+            return classed_html(syntaxes, syntax, code);
+        }
+        let lines = classed_lines(syntaxes, syntax, code)?;
+        return Ok(format!("<pre class=\"hl-code\">{}</pre>", line_spans(fence, &lines, None)));
+    }
+
+    let theme = fence.theme.as_deref().or(options.theme.as_deref()).unwrap_or(DEFAULT_THEME);
+    let theme = find_theme(&sets.themes, theme)?;
+    if !by_line {
+        return highlighted_html_for_string(code, syntaxes, syntax, theme).map_err(|e| e.to_string());
+    }
+    let lines = styled_lines(syntaxes, syntax, theme, code)?;
+    let (pre, _) = start_highlighted_html_snippet(theme);
+    Ok(format!("{pre}{}</pre>\n", line_spans(fence, &lines, Some(theme))))
+}
+
+/// The syntaxes and themes code blocks are highlighted with
+struct HighlightSets {
+    syntaxes: SyntaxSet,
+    themes: ThemeSet,
+}
+
+/// The sets of the project in `options.dir`, or of the dump in `options.dump_dir`, loaded the first time
+/// they are used. They are loaded again when one of their files changes, for long-running rust-analyzer
+/// processes. Errors are not kept: the next page tries again.
+fn highlight_sets(options: &HighlightOptions) -> Result<Arc<HighlightSets>, String> {
+    type Key = (Option<PathBuf>, Option<PathBuf>);
+    type Cache = Mutex<HashMap<Key, (Vec<FileStamp>, Arc<HighlightSets>)>>;
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+
+    let key = (options.dir.clone(), options.dump_dir.clone());
+    let stamps = file_stamps(options);
+    let mut cache = CACHE.get_or_init(Default::default).lock().unwrap();
+    if let Some((cached_stamps, sets)) = cache.get(&key) {
+        if *cached_stamps == stamps {
+            return Ok(sets.clone());
+        }
+    }
+
+    let dump = options.dump_dir.as_deref().and_then(load_dump);
+    let sets = match dump {
+        Some(sets) => sets,
+        None => HighlightSets {
+            syntaxes: syntax_set(options.dir.as_deref())?,
+            themes: theme_set(options.dir.as_deref())?,
+        },
+    };
+    let sets = Arc::new(sets);
+    cache.insert(key, (stamps, sets.clone()));
+    Ok(sets)
+}
+
+/// A file the highlight sets are loaded from, with its modification time
+type FileStamp = (PathBuf, Option<SystemTime>);
+
+/// The files of the dump and of the project directory, with their modification times
+fn file_stamps(options: &HighlightOptions) -> Vec<FileStamp> {
+    let dump = options
+        .dump_dir
+        .iter()
+        .flat_map(|dump_dir| [dump_dir.join(SYNTAX_DUMP), dump_dir.join(THEME_DUMP)]);
+    let project = options.dir.as_deref().map(highlight_files).unwrap_or_default();
+    dump.chain(project)
+        .map(|file| {
+            let modified = std::fs::metadata(&file).and_then(|metadata| metadata.modified()).ok();
+            (file, modified)
+        })
+        .collect()
+}
+
+/// The `.sublime-syntax` and `.tmTheme` files of the project directory `dir` and its subdirectories.
+/// The macros track them, so that the pages are compiled again when they change.
+pub fn highlight_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files: Vec<PathBuf> = vec![];
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            files.extend(highlight_files(&path));
+        } else if path.extension().is_some_and(|extension| extension == "sublime-syntax" || extension == "tmTheme") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+fn load_dump(dump_dir: &Path) -> Option<HighlightSets> {
+    Some(HighlightSets {
+        syntaxes: from_uncompressed_dump_file(dump_dir.join(SYNTAX_DUMP)).ok()?,
+        themes: from_uncompressed_dump_file(dump_dir.join(THEME_DUMP)).ok()?,
+    })
+}
+
+/// Compile the syntaxes and themes of the project in `project_dir` to `OUT_DIR`, from a build script,
+/// so that the macros don't have to load them from its [`highlight_dir`].
+///
+/// ```rust,ignore
+/// parse_markdown::dump_highlight_sets(".").unwrap();
+/// ```
+pub fn dump_highlight_sets(project_dir: impl AsRef<Path>) -> io::Result<()> {
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set, call this from a build script"))?;
+    let dir = highlight_dir(project_dir.as_ref());
+    if let Some(dir) = &dir {
+        println!("cargo:rerun-if-changed={}", dir.display());
+    }
+    write_dump(dir.as_deref(), Path::new(&out_dir))
+}
+
+pub(crate) fn write_dump(dir: Option<&Path>, dump_dir: &Path) -> io::Result<()> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+    let syntaxes = syntax_set(dir).map_err(invalid)?;
+    let themes = theme_set(dir).map_err(invalid)?;
+    dump_to_uncompressed_file(&syntaxes, dump_dir.join(SYNTAX_DUMP)).map_err(io::Error::other)?;
+    dump_to_uncompressed_file(&themes, dump_dir.join(THEME_DUMP)).map_err(io::Error::other)?;
+    Ok(())
+}
+
+/// syntect's syntaxes, with the syntaxes of the project
+fn syntax_set(dir: Option<&Path>) -> Result<SyntaxSet, String> {
+    let defaults = SyntaxSet::load_defaults_newlines();
+    let Some(dir) = dir else {
+        return Ok(defaults);
+    };
+    let mut builder = defaults.into_builder();
+    builder
+        .add_from_folder(dir, true)
+        .map_err(|e| format!("unable to load the syntaxes of {}: {e}", dir.display()))?;
+    Ok(builder.build())
+}
+
+/// A set with only [`TOML_SYNTAX`].
+///
+/// It is kept apart from syntect's syntaxes: adding a syntax to them links them all again,
+/// which takes seconds in the unoptimized build of a proc macro.
+fn toml_syntaxes() -> &'static SyntaxSet {
+    static TOML: OnceLock<SyntaxSet> = OnceLock::new();
+    TOML.get_or_init(|| {
+        let mut builder = SyntaxSetBuilder::new();
+        builder.add(SyntaxDefinition::load_from_str(TOML_SYNTAX, true, None).expect("the TOML syntax is valid"));
+        builder.build()
+    })
+}
+
+/// syntect's themes, with the themes of the project
+fn theme_set(dir: Option<&Path>) -> Result<ThemeSet, String> {
+    let mut themes = ThemeSet::load_defaults();
+    if let Some(dir) = dir {
+        themes
+            .add_from_folder(dir)
+            .map_err(|e| format!("unable to load the themes of {}: {e}", dir.display()))?;
+    }
+    Ok(themes)
+}
+
+/// A theme by name: the name of one of syntect's themes, or the file name of a theme of the project
+fn find_theme<'a>(themes: &'a ThemeSet, name: &str) -> Result<&'a Theme, String> {
+    themes.themes.get(name).ok_or_else(|| {
+        let known: Vec<&str> = themes.themes.keys().map(String::as_str).collect();
+        format!("unknown theme `{name}`, expected one of {}", known.join(", "))
+    })
+}
+
+fn classed_html(syntaxes: &SyntaxSet, syntax: &SyntaxReference, code: &str) -> Result<String, String> {
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line).map_err(|e| e.to_string())?;
+    }
+    Ok(format!("<pre class=\"hl-code\">{}</pre>", generator.finalize()))
+}
+
+/// The HTML of each line of the code, with inline styles. The spans of a line are closed at its end
+fn styled_lines(syntaxes: &SyntaxSet, syntax: &SyntaxReference, theme: &Theme, code: &str) -> Result<Vec<String>, String> {
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let regions = highlighter.highlight_line(line, syntaxes).map_err(|e| e.to_string())?;
+            styled_line_to_highlighted_html(&regions, IncludeBackground::No).map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+/// The HTML of each line of the code, with classes. The scopes that continue on the next line,
+/// like a multiline comment, are closed at the end of the line and opened again on the next one
+fn classed_lines(syntaxes: &SyntaxSet, syntax: &SyntaxReference, code: &str) -> Result<Vec<String>, String> {
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let mut html: String = stack
+                .as_slice()
+                .iter()
+                .map(|scope| format!("<span class=\"{}\">", scope_classes(*scope)))
+                .collect();
+            let ops = state.parse_line(line, syntaxes).map_err(|e| e.to_string())?;
+            let (spans, _) =
+                line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut stack).map_err(|e| e.to_string())?;
+            html += &spans;
+            html += &"</span>".repeat(stack.len());
+            Ok(html)
+        })
+        .collect()
+}
+
+/// The classes of a scope, like `hl-comment hl-block hl-rust` for `comment.block.rust`
+fn scope_classes(scope: Scope) -> String {
+    let classes: Vec<String> = scope.build_string().split('.').map(|atom| format!("hl-{atom}")).collect();
+    classes.join(" ")
+}
+
+/// Wrap each line in a `span.hl-line`, after its `span.hl-lineno` with `linenos`.
+/// Highlighted lines also have the class `hl-highlighted`.
+/// With a theme, the spans are styled inline, otherwise they are styled by the stylesheet.
+fn line_spans(fence: &FenceInfo, lines: &[String], theme: Option<&Theme>) -> String {
+    let width = lines.len().to_string().len();
+    let mut html = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        let highlighted = fence.hl_lines.iter().any(|(start, end)| (*start..=*end).contains(&number));
+        let class = if highlighted { "hl-line hl-highlighted" } else { "hl-line" };
+        match theme {
+            Some(theme) if highlighted => {
+                let background = theme.settings.line_highlight.map_or(LINE_HIGHLIGHT.to_string(), css_color);
+                html += &format!("<span class=\"{class}\" style=\"{LINE_STYLE} background-color: {background};\">");
+            }
+            Some(_) => html += &format!("<span class=\"{class}\" style=\"{LINE_STYLE}\">"),
+            None => html += &format!("<span class=\"{class}\">"),
+        }
+        if fence.linenos {
+            match theme {
+                Some(theme) => {
+                    let color = theme.settings.gutter_foreground.map(|color| format!(" color: {};", css_color(color)));
+                    let color = color.unwrap_or_default();
+                    html += &format!("<span class=\"hl-lineno\" style=\"{LINENO_STYLE}{color}\">");
+                }
+                None => html += "<span class=\"hl-lineno\">",
+            }
+            html += &format!("{number:>width$}</span>");
+        }
+        // The tokens of the line are one flex item
+        html += &format!("<span>{line}</span></span>");
+    }
+    html
+}
+
+fn css_color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, color.a)
+}
+
+/// The rules of the lines of blocks with `linenos` or `hl_lines`, for a theme
+fn line_css(theme: &Theme) -> String {
+    let background = theme.settings.line_highlight.map_or(LINE_HIGHLIGHT.to_string(), css_color);
+    let mut css = format!(".hl-highlighted {{\n background-color: {background};\n}}\n");
+    if let Some(color) = theme.settings.gutter_foreground {
+        css += &format!(".hl-lineno {{\n color: {};\n}}\n", css_color(color));
+    }
+    css
+}
+
+/// The stylesheet of code blocks highlighted with classes. The `light` and `dark` themes
+/// are in `prefers-color-scheme` media queries, so that they override the default one.
+///
+/// Themes are found among syntect's themes and those of `dir`, the directory of the themes of the project.
+pub fn highlight_css(themes: &HighlightThemes, dir: Option<&Path>) -> io::Result<String> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let theme_set = theme_set(dir).map_err(invalid)?;
+    let theme_css = |name: &str| {
+        let theme = find_theme(&theme_set, name).map_err(invalid)?;
+        let css = css_for_theme_with_class_style(theme, CLASS_STYLE).map_err(io::Error::other)?;
+        Ok::<_, io::Error>(css + &line_css(theme))
+    };
+
+    let mut css = format!(".hl-line {{\n {LINE_STYLE}\n}}\n.hl-lineno {{\n {LINENO_STYLE}\n}}\n\n");
+    css += &theme_css(&themes.default)?;
+    for (scheme, theme) in [("light", &themes.light), ("dark", &themes.dark)] {
+        if let Some(theme) = theme {
+            css += &format!("\n@media (prefers-color-scheme: {scheme}) {{\n{}}}\n", theme_css(theme)?);
+        }
+    }
+    Ok(css)
+}
+
+/// Write the stylesheet of code blocks highlighted with classes to `highlight.css`,
+/// in the assets directory of the dioxus project in `project_dir`, from a build script.
+///
+/// The assets directory is the `asset_dir` of `Dioxus.toml`, or `assets`. The themes of the project,
+/// in its [`highlight_dir`], can be used. Returns the path of the stylesheet.
+///
+/// ```rust,ignore
+/// let themes = HighlightThemes {
+///     default: "InspiredGitHub".to_string(),
+///     dark: Some("base16-ocean.dark".to_string()),
+///     ..Default::default()
+/// };
+/// parse_markdown::write_highlight_css(".", &themes).unwrap();
+/// ```
+pub fn write_highlight_css(project_dir: impl AsRef<Path>, themes: &HighlightThemes) -> io::Result<PathBuf> {
+    let project_dir = project_dir.as_ref();
+    let dioxus_toml = std::fs::read_to_string(project_dir.join("Dioxus.toml")).unwrap_or_default();
+    let asset_dir = asset_dir(&dioxus_toml);
+    let dir = project_dir.join(asset_dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join("highlight.css");
+    let css = highlight_css(themes, highlight_dir(project_dir).as_deref())?;
+    // Don't touch the file when it didn't change, so that it doesn't trigger the watcher of `dx serve`
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(css.as_str()) {
+        std::fs::write(&path, css)?;
+    }
+    Ok(path)
+}
+
+/// The directory of the syntaxes and themes of the project in `project_dir`, set in its `Cargo.toml`:
+///
+/// ```toml
+/// [package.metadata.markdown]
+/// highlight_dir = "highlight"
+/// ```
+pub fn highlight_dir(project_dir: &Path) -> Option<PathBuf> {
+    let cargo_toml = std::fs::read_to_string(project_dir.join("Cargo.toml")).ok()?;
+    let dir = toml_text(&cargo_toml, "package.metadata.markdown.highlight_dir")?;
+    Some(project_dir.join(dir))
+}
+
+/// The assets directory set in `Dioxus.toml`
+pub(crate) fn asset_dir(dioxus_toml: &str) -> String {
+    toml_text(dioxus_toml, "application.asset_dir").unwrap_or_else(|| "assets".to_string())
+}
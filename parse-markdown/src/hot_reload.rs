@@ -0,0 +1,306 @@
+//! Hot reload of markdown pages.
+//!
+//! `md_page!` expands to a single rsx body, whose root template is keyed by the location
+//! of the macro call. When an edit only touches the static parts of that template (markdown
+//! text and the elements around it), the new template can be sent to the running app with
+//! dioxus's hot reload protocol. Anything else, like a change in the rust code of a
+//! `{{ }}` block, needs a rebuild.
+//!
+//! The templates are sent to desktop apps by the `md-hot-reload` watcher. Web apps only listen
+//! to the devserver of `dx serve`, which doesn't know about markdown pages: they don't get them.
+//!
+//! Templates are built from `&'static` data, so each hot reloaded template is leaked.
+//! This is only meant for a development tool.
+
+use dioxus_core::internal::{
+    FmtSegment, FmtedSegments, HotReloadDynamicAttribute, HotReloadDynamicNode, HotReloadLiteral,
+    HotReloadTemplateWithLocation, HotReloadedTemplate, TemplateGlobalKey,
+};
+use dioxus_core::{TemplateAttribute, TemplateNode};
+use dioxus_rsx::{
+    AttributeName, BodyNode, Element, ElementName, HotLiteral, HotReloadFormattedSegment, Segment,
+    TemplateBody,
+};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{ParseStream, Parser};
+use syn::{LitStr, Token};
+
+use crate::{parse_page, ParseOptions};
+
+/// The location of a `md_page!` call, as `file!()`, `line!()` and `column!()` see it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageLocation {
+    /// Path of the rust file, relative to the workspace root
+    pub file: String,
+    /// 1-based line of the macro call
+    pub line: usize,
+    /// 1-based column (in characters) of the macro call
+    pub column: usize,
+}
+
+/// A `md_page!` call of a rust file.
+#[derive(Debug, Clone)]
+pub struct PageCall {
+    /// Path of the page, relative to the crate of the rust file
+    pub page: String,
+    pub location: PageLocation,
+    /// The options given to the macro after the path
+    pub options: ParseOptions,
+}
+
+/// What to do after a markdown page was edited.
+#[derive(Debug)]
+pub enum PageChange {
+    Unchanged,
+    /// The new root template of the page
+    Template(Box<HotReloadTemplateWithLocation>),
+    /// The page changed in a way that can't be hot reloaded: the crate must be rebuilt
+    Rebuild,
+}
+
+/// Compare the page the app was compiled with (`compiled`) to its current source (`current`).
+///
+/// The dynamic parts of the page (components, expressions, `{=expr}` interpolations,
+/// non-literal attributes) are created by the compiled code, so they must stay the same:
+/// the new template can only reorder the static parts around them.
+///
+/// Both pages are parsed with the `options` the macro compiled the page with.
+pub fn diff_pages(
+    compiled: &str,
+    current: &str,
+    options: &ParseOptions,
+    location: &PageLocation,
+) -> PageChange {
+    if compiled == current {
+        return PageChange::Unchanged;
+    }
+    let (Ok(compiled), Ok(current)) = (parse_page(compiled, options), parse_page(current, options)) else {
+        // Let the compiler report the error
+        return PageChange::Rebuild;
+    };
+    let (compiled, current) = (&compiled.body.body, &current.body.body);
+
+    if current.implicit_key().is_some() {
+        return PageChange::Rebuild;
+    }
+    if tokens(compiled.dynamic_text_segments.iter()) != tokens(current.dynamic_text_segments.iter())
+        || tokens(compiled.dynamic_attributes().map(|a| a.rendered_as_dynamic_attr()))
+            != tokens(current.dynamic_attributes().map(|a| a.rendered_as_dynamic_attr()))
+    {
+        return PageChange::Rebuild;
+    }
+
+    // Formatted text is rebuilt from the template, every other dynamic node is reused
+    let reused = |body: &TemplateBody| -> Vec<(usize, String)> {
+        body.dynamic_nodes()
+            .filter(|node| !matches!(node, BodyNode::Text(_)))
+            .map(|node| (node.get_dyn_idx(), node.to_token_stream().to_string()))
+            .collect()
+    };
+    let (compiled_nodes, current_nodes) = (reused(compiled), reused(current));
+    if compiled_nodes.len() != current_nodes.len()
+        || compiled_nodes.iter().zip(&current_nodes).any(|(a, b)| a.1 != b.1)
+    {
+        return PageChange::Rebuild;
+    }
+    let mut compiled_ids = compiled_nodes.into_iter().map(|(id, _)| id);
+
+    let dynamic_nodes = current
+        .dynamic_nodes()
+        .map(|node| match node {
+            BodyNode::Text(text) => HotReloadDynamicNode::Formatted(fmted_segments(&text.input)),
+            _ => HotReloadDynamicNode::Dynamic(compiled_ids.next().unwrap()),
+        })
+        .collect();
+    let dynamic_attributes = (0..current.attr_paths.len())
+        .map(HotReloadDynamicAttribute::Dynamic)
+        .collect();
+    let component_values = current
+        .literal_component_properties()
+        .map(hot_reload_literal)
+        .collect();
+
+    let Some(roots) = current.roots.iter().map(template_node).collect::<Option<Vec<_>>>() else {
+        return PageChange::Rebuild;
+    };
+
+    PageChange::Template(Box::new(HotReloadTemplateWithLocation {
+        key: TemplateGlobalKey {
+            file: location.file.clone(),
+            line: location.line,
+            column: location.column,
+            index: 0,
+        },
+        template: HotReloadedTemplate::new(
+            None,
+            dynamic_nodes,
+            dynamic_attributes,
+            component_values,
+            Vec::leak(roots),
+        ),
+    }))
+}
+
+fn tokens<T: ToTokens>(items: impl Iterator<Item = T>) -> String {
+    quote!(#(#items)*).to_string()
+}
+
+fn fmted_segments(input: &HotReloadFormattedSegment) -> FmtedSegments {
+    let mut ids = input.dynamic_node_indexes.iter();
+    let segments = input
+        .segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Literal(value) => FmtSegment::Literal { value: leak(value) },
+            Segment::Formatted(_) => FmtSegment::Dynamic { id: ids.next().unwrap().get() },
+        })
+        .collect();
+    FmtedSegments::new(segments)
+}
+
+fn hot_reload_literal(literal: &HotLiteral) -> HotReloadLiteral {
+    match literal {
+        HotLiteral::Fmted(input) => HotReloadLiteral::Fmted(fmted_segments(input)),
+        HotLiteral::Float(f) => HotReloadLiteral::Float(f.base10_parse().unwrap_or_default()),
+        HotLiteral::Int(i) => HotReloadLiteral::Int(i.base10_parse().unwrap_or_default()),
+        HotLiteral::Bool(b) => HotReloadLiteral::Bool(b.value),
+    }
+}
+
+/// The template node the rsx macro would generate for `node`,
+/// or `None` if we can't be sure to name its element and attributes like dioxus-html does.
+fn template_node(node: &BodyNode) -> Option<TemplateNode> {
+    match node {
+        BodyNode::Element(element) => template_element(element),
+        BodyNode::Text(text) if text.is_static() => Some(TemplateNode::Text {
+            text: leak(&text.input.to_static()?),
+        }),
+        _ => Some(TemplateNode::Dynamic { id: node.get_dyn_idx() }),
+    }
+}
+
+fn template_element(element: &Element) -> Option<TemplateNode> {
+    let ElementName::Ident(tag) = &element.name else {
+        return None;
+    };
+    let tag = tag.to_string();
+    if !HTML_ELEMENTS.contains(&tag.as_str()) {
+        return None;
+    }
+
+    let attrs = element
+        .merged_attributes
+        .iter()
+        .map(|attr| {
+            let Some((name, value)) = attr.as_static_str_literal() else {
+                return Some(TemplateAttribute::Dynamic { id: attr.get_dyn_idx() });
+            };
+            let name = match name {
+                AttributeName::BuiltIn(name) => {
+                    let name = name.to_string();
+                    let name = name.trim_start_matches("r#");
+                    is_plain_attribute(&tag, name).then_some(name)?.to_string()
+                }
+                AttributeName::Custom(name) => name.value(),
+                AttributeName::Spread(_) => return None,
+            };
+            Some(TemplateAttribute::Static {
+                name: leak(&name),
+                value: leak(&value.to_static()?),
+                namespace: None,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let children = element
+        .children
+        .iter()
+        .map(template_node)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(TemplateNode::Element {
+        tag: leak(&tag),
+        namespace: None,
+        attrs: Vec::leak(attrs),
+        children: Vec::leak(children),
+    })
+}
+
+/// HTML elements whose dioxus-html tag name is their rust name
+const HTML_ELEMENTS: &[&str] = &[
+    "a", "article", "aside", "b", "blockquote", "br", "button", "code", "del", "details", "div",
+    "em", "figcaption", "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr",
+    "i", "img", "input", "kbd", "li", "main", "mark", "nav", "ol", "p", "pre", "s", "section",
+    "small", "span", "strong", "sub", "summary", "sup", "table", "tbody", "td", "th", "thead",
+    "tr", "u", "ul",
+];
+
+/// Attributes whose dioxus-html name is their rust name, without a namespace.
+/// Others may be renamed (`aria_label` is `aria-label`) or be style properties.
+fn is_plain_attribute(tag: &str, name: &str) -> bool {
+    matches!(
+        name,
+        "id" | "class" | "style" | "title" | "lang" | "dir" | "role" | "dangerous_inner_html"
+    ) || matches!(
+        (tag, name),
+        ("a", "href" | "target" | "rel")
+            | ("img", "src" | "alt" | "width" | "height")
+            | ("input", "type" | "value" | "checked" | "disabled")
+            | ("button", "type" | "disabled")
+            | ("td" | "th", "colspan" | "rowspan" | "align")
+            | ("ol", "start")
+    )
+}
+
+fn leak(text: &str) -> &'static str {
+    String::leak(text.to_string())
+}
+
+/// Find the `md_page!` calls of a rust file: the path of each page, the location of the call
+/// and its options.
+///
+/// `file` is the path of the rust file relative to the workspace root, like `file!()`.
+/// Calls whose arguments don't parse are skipped: the compiler reports them.
+pub fn find_page_calls(file: &str, source: &str) -> Vec<PageCall> {
+    let mut calls = vec![];
+    if let Ok(tokens) = source.parse() {
+        collect_page_calls(file, tokens, &mut calls);
+    }
+    calls
+}
+
+fn collect_page_calls(file: &str, tokens: TokenStream, calls: &mut Vec<PageCall>) {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for (index, token) in tokens.iter().enumerate() {
+        match (token, tokens.get(index + 1), tokens.get(index + 2)) {
+            (TokenTree::Ident(name), Some(TokenTree::Punct(bang)), Some(TokenTree::Group(args)))
+                if name == "md_page" && bang.as_char() == '!' =>
+            {
+                let Ok((page, options)) = page_call_args.parse2(args.stream()) else {
+                    continue;
+                };
+                let start = name.span().start();
+                calls.push(PageCall {
+                    page,
+                    location: PageLocation {
+                        file: file.to_string(),
+                        line: start.line,
+                        column: start.column + 1,
+                    },
+                    options,
+                });
+            }
+            (TokenTree::Group(group), ..) => collect_page_calls(file, group.stream(), calls),
+            _ => {}
+        }
+    }
+}
+
+/// The arguments of `md_page!`, like the macro parses them
+fn page_call_args(input: ParseStream) -> syn::Result<(String, ParseOptions)> {
+    let page: LitStr = input.parse()?;
+    if !input.is_empty() {
+        input.parse::<Token![,]>()?;
+    }
+    Ok((page.value(), input.parse()?))
+}
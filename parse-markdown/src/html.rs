@@ -0,0 +1,206 @@
+//! Raw HTML written in markdown, like `<kbd>Ctrl</kbd>` or `<details><summary>More</summary>`.
+//!
+//! Tags are translated to rsx elements, so that the markdown between an opening and a closing tag
+//! becomes their children. HTML that can't be translated, like `<script>`, `<svg>` or a broken tag,
+//! is rendered as is with `dangerous_inner_html`.
+
+use dioxus_rsx::BodyNode;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Ident};
+
+use crate::escape_text;
+
+/// The elements of dioxus that can be written in markdown. Elements with a `-` are web components.
+const ELEMENTS: &[&str] = &[
+    "a", "abbr", "address", "area", "article", "aside", "audio", "b", "bdi", "bdo", "blockquote", "br",
+    "button", "caption", "cite", "code", "col", "colgroup", "data", "datalist", "dd", "del", "details",
+    "dfn", "dialog", "div", "dl", "dt", "em", "embed", "fieldset", "figcaption", "figure", "footer",
+    "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup", "hr", "i", "iframe", "img", "input",
+    "ins", "kbd", "label", "legend", "li", "main", "map", "mark", "menu", "meter", "nav", "object", "ol",
+    "optgroup", "option", "output", "p", "picture", "pre", "progress", "q", "rp", "rt", "ruby", "s",
+    "samp", "section", "select", "small", "source", "span", "strong", "sub", "summary", "sup", "table",
+    "tbody", "td", "textarea", "tfoot", "th", "thead", "time", "tr", "track", "u", "ul", "var", "video",
+    "wbr",
+];
+
+/// Elements without content or closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "br", "col", "embed", "hr", "img", "input", "source", "track", "wbr",
+];
+
+/// A piece of raw HTML.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum HtmlToken {
+    /// An opening tag, with its attributes. `closed` for void elements and `<tag />`
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+        closed: bool,
+    },
+    End(String),
+    Text(String),
+}
+
+/// Split raw HTML into tags and text, or `None` if some of it can't be translated to rsx.
+/// Comments are left out.
+pub(crate) fn html_tokens(html: &str) -> Option<Vec<HtmlToken>> {
+    let mut tokens = vec![];
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.split_once("-->")?.1;
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let (name, after) = after.split_once('>')?;
+            tokens.push(HtmlToken::End(element_name(name.trim())?));
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (token, after) = start_tag(after)?;
+            tokens.push(token);
+            rest = after;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            // Line breaks between block elements are not part of the content
+            if !(text.trim().is_empty() && text.contains('\n')) {
+                tokens.push(HtmlToken::Text(decode_entities(text)));
+            }
+            rest = &rest[end..];
+        }
+    }
+    Some(tokens)
+}
+
+/// Parse an opening tag, after its `<`
+fn start_tag(html: &str) -> Option<(HtmlToken, &str)> {
+    let name_end = html
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(html.len());
+    let name = element_name(&html[..name_end])?;
+    let mut rest = &html[name_end..];
+    let mut attributes = vec![];
+    let closed = loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            rest = after;
+            break true;
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            rest = after;
+            break VOID_ELEMENTS.contains(&name.as_str());
+        }
+        let attribute_end = rest.find(|c: char| c.is_whitespace() || "=>/\"'".contains(c))?;
+        if attribute_end == 0 {
+            return None;
+        }
+        let attribute = rest[..attribute_end].to_ascii_lowercase();
+        rest = rest[attribute_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, after) = match after.chars().next()? {
+                    quote @ ('"' | '\'') => after[1..].split_once(quote)?,
+                    _ => {
+                        let end = after.find(|c: char| c.is_whitespace() || c == '>')?;
+                        after.split_at(end)
+                    }
+                };
+                rest = after;
+                decode_entities(value)
+            }
+            // A boolean attribute, like `open`
+            None => String::new(),
+        };
+        attributes.push((attribute, value));
+    };
+    Some((HtmlToken::Start { name, attributes, closed }, rest))
+}
+
+/// The lowercase name of an element that can be rendered by dioxus
+fn element_name(name: &str) -> Option<String> {
+    let name = name.to_ascii_lowercase();
+    let is_web_component = name.contains('-')
+        && name.split('-').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic()) && syn::parse_str::<Ident>(part).is_ok()
+        });
+    (ELEMENTS.contains(&name.as_str()) || is_web_component).then_some(name)
+}
+
+/// The rsx element of an opening tag. Attributes are written with their HTML name.
+pub(crate) fn html_element(name: &str, attributes: &[(String, String)]) -> BodyNode {
+    let parts = name.split('-').map(|part| Ident::new(part, Span::call_site()));
+    let name: TokenStream = quote!(#(#parts)-*);
+    let attributes = attributes.iter().map(|(attribute, value)| {
+        let value = escape_text(value);
+        quote!(#attribute: #value,)
+    });
+    parse_quote! {
+        #name {
+            #(#attributes)*
+        }
+    }
+}
+
+/// HTML that can't be translated, rendered as is
+pub(crate) fn raw_html(html: &str, block: bool) -> BodyNode {
+    let html = escape_text(html);
+    if block {
+        parse_quote!(div { dangerous_inner_html: #html })
+    } else {
+        parse_quote!(span { dangerous_inner_html: #html })
+    }
+}
+
+/// Replace the character references of HTML text, like `&amp;` or `&#39;`
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded += &rest[..start];
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .split_once(';')
+            .and_then(|(entity, _)| Some((entity, decode_entity(entity)?)));
+        match entity {
+            Some((entity, char)) => {
+                decoded.push(char);
+                rest = &rest[entity.len() + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded + rest
+}
+
+/// The character of a reference, without its `&` and `;`.
+/// Like in CommonMark, `&#0;` and numbers that are not a valid character decode to U+FFFD.
+fn decode_entity(entity: &str) -> Option<char> {
+    let code = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+        if !(1..=6).contains(&hex.len()) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(decimal) = entity.strip_prefix('#') {
+        if !(1..=7).contains(&decimal.len()) || !decimal.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        decimal.parse().ok()?
+    } else {
+        return match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{A0}'),
+            _ => None,
+        };
+    };
+    match char::from_u32(code) {
+        Some('\0') | None => Some(char::REPLACEMENT_CHARACTER),
+        char => char,
+    }
+}
@@ -1,8 +1,8 @@
-use std::{iter::Peekable, vec};
-use regex::Regex;
+use std::{iter::Peekable, ops::Range, vec};
 
-use dioxus_rsx::{CallBody, BodyNode, TemplateBody, RsxBlock};
+use dioxus_rsx::{CallBody, BodyNode, IfmtInput, TemplateBody, RsxBlock};
 use pulldown_cmark::{Alignment, Event, Options, Parser, Tag};
+use proc_macro2::{Literal, TokenStream};
 use quote::quote;
 use syn::{
     Ident,
@@ -12,37 +12,38 @@ use syn::{
     parse::Parse,
 };
 
-use syntect::highlighting::ThemeSet;
-use syntect::parsing::SyntaxSet;
-
-
-//#[proc_macro]
-//pub fn md_page(input: TokenStream) -> TokenStream {
-//    let file_path = parse_macro_input!(input as LitStr);
-//
-//    // Get the manifest directory of the crate using the macro
-//    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
-//
-//    // Combine with the provided path
-//    let full_path = PathBuf::from(manifest_dir)
-//        .join("src")
-//        .join(file_path.value());
-//
-//    let content = fs::read_to_string(full_path).unwrap();
-//    let items = extract_items(&content);
-//
-//    let children: Vec<BodyNode> = items
-//        .into_iter()
-//        .flat_map(|x| x.to_body_nodes())
-//        .collect();
-//
-//    let template_body = TemplateBody::new(children);
-//
-//
-//    quote!(
-//        #template_body
-//    ).into()
-//}
+mod check;
+mod components;
+mod error;
+mod footnotes;
+mod front_matter;
+mod highlight;
+mod html;
+mod links;
+mod options;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+mod scanner;
+mod tasks;
+mod toc;
+pub use check::{check_links, PageIndex};
+pub use components::{ComponentMap, MarkdownElement};
+use components::override_node;
+pub use error::MarkdownError;
+use footnotes::{footnote_item, footnote_reference, footnote_section, set_definition, Footnote};
+pub use front_matter::FrontMatter;
+use front_matter::split_front_matter;
+use highlight::{fence_info, highlight};
+pub use highlight::{dump_highlight_sets, highlight_css, highlight_dir, highlight_files, write_highlight_css, HighlightOptions, HighlightThemes};
+use html::{html_element, html_tokens, raw_html, HtmlToken};
+use links::LinkTarget;
+pub use links::LinkOptions;
+pub use options::ParseOptions;
+use scanner::{expression_end, find_rsx_blocks, RsxBlockRange};
+pub use tasks::{task_state, TaskOptions};
+use tasks::checkbox;
+pub use toc::{toc_tree, TocNode};
+use toc::{heading_attributes, slug, toc_list, unique_slug, TOC_MARKER};
 
 struct RsxBlockInner(RsxBlock);
 
@@ -54,65 +55,218 @@ impl Parse for RsxBlockInner {
     }
 }
 
-pub fn parse(content: &str) -> CallBody {
-    let items = extract_items(&content);
+/// A syn error, with the byte offset in the page of the text syn was given.
+type LocatedError = (usize, syn::Error);
 
-    let children: Vec<BodyNode> = items
-        .into_iter()
-        .flat_map(|x| x.to_body_nodes())
-        .collect();
+/// A parsed markdown page.
+#[derive(Debug)]
+pub struct Page {
+    pub body: CallBody,
+    /// The metadata from the front matter of the page
+    pub meta: FrontMatter,
+    /// Errors found in the page. Always empty unless [`ParseOptions::lenient`] is set.
+    pub diagnostics: Vec<MarkdownError>,
+    /// Every heading of the page, in order
+    pub headings: Vec<PageHeading>,
+    /// Every link of the page, in order
+    pub links: Vec<PageLink>,
+    /// Whether each task of the task lists of the page is checked, in order
+    pub tasks: Vec<bool>,
+}
+
+/// A link written in a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLink {
+    /// The destination, as written in the markdown
+    pub dest: String,
+    /// Byte offset of the link in the page
+    pub offset: usize,
+}
+
+/// A heading of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageHeading {
+    /// 1 to 6
+    pub level: u8,
+    /// The text of the heading, without its formatting
+    pub text: String,
+    /// The `id` of the heading
+    pub anchor: String,
+}
+
+/// The headings, links, footnotes and tasks found while parsing markdown
+#[derive(Debug, Default)]
+struct Outline {
+    headings: Vec<PageHeading>,
+    links: Vec<PageLink>,
+    /// Footnotes, by number
+    footnotes: Vec<Footnote>,
+    tasks: Vec<bool>,
+    /// Whether a `[[toc]]` marker was found
+    has_toc: bool,
+}
+
+/// Parse a page, stopping at the first error.
+pub fn parse(content: &str) -> Result<CallBody, MarkdownError> {
+    parse_page(content, &ParseOptions::default())
+        .map(|page| page.body)
+        .map_err(|mut errors| errors.remove(0))
+}
+
+/// Parse a page, collecting every error instead of stopping at the first one.
+///
+/// Unless the page is parsed in lenient mode, any error makes the whole parse fail.
+pub fn parse_page(content: &str, options: &ParseOptions) -> Result<Page, Vec<MarkdownError>> {
+    let mut errors = vec![];
+    let (meta, markdown) = split_front_matter(content, &mut errors);
+    let items = extract_items(&markdown, &mut errors);
+
+    let render = |toc: &[PageHeading]| {
+        let mut children: Vec<BodyNode> = vec![];
+        let mut errors = vec![];
+        let mut outline = Outline::default();
+        for item in &items {
+            children.extend(item.to_body_nodes(options, toc, &mut errors, &mut outline));
+        }
+        (children, errors, outline)
+    };
+    let (mut children, mut item_errors, mut outline) = render(&[]);
+    if outline.has_toc {
+        // The table of contents needs the headings that follow it: render the page again
+        (children, item_errors, outline) = render(&outline.headings);
+    }
+    errors.extend(item_errors);
+    children.extend(footnote_section(&outline.footnotes));
+
+    let diagnostics = located_errors(content, errors);
+    if !diagnostics.is_empty() && !options.lenient {
+        return Err(diagnostics);
+    }
 
     let template_body = TemplateBody::new(children);
-    CallBody::new(template_body)
+    Ok(Page {
+        body: CallBody::new(template_body),
+        meta,
+        diagnostics,
+        headings: outline.headings,
+        links: outline.links,
+        tasks: outline.tasks,
+    })
+}
+
+/// Parse only the front matter of a page.
+pub fn parse_front_matter(content: &str) -> Result<FrontMatter, Vec<MarkdownError>> {
+    let mut errors = vec![];
+    let (meta, _) = split_front_matter(content, &mut errors);
+    if errors.is_empty() {
+        Ok(meta)
+    } else {
+        Err(located_errors(content, errors))
+    }
+}
 
+fn located_errors(content: &str, errors: Vec<LocatedError>) -> Vec<MarkdownError> {
+    errors
+        .into_iter()
+        .flat_map(|(offset, error)| {
+            error
+                .into_iter()
+                .map(move |e| MarkdownError::from_syn(content, offset, &e))
+        })
+        .collect()
 }
 
-fn extract_items(text: &str) -> Vec<Item> {
-    let re = Regex::new(r"(?sU)\{\{(.*)\}\}").unwrap();
-        
-    // Collect all matches into a vector
-    //re.captures_iter(text)
-    //    .map(|cap| Item::new(&cap[1], &cap[2]))
-    //    .collect()    let mut last_end = 0;
+/// Marks the place of an inline `{{ }}` block in markdown text: `\u{E000}index\u{E001}`.
+/// Markdown leaves private use characters alone. Pages may contain them too, like icon fonts:
+/// placeholders are recognized by their byte range, not by their text.
+const PLACEHOLDER_START: char = '\u{E000}';
+const PLACEHOLDER_END: char = '\u{E001}';
 
+fn extract_items(text: &str, errors: &mut Vec<LocatedError>) -> Vec<Item> {
     let mut last_end = 0;
     let mut result = Vec::new();
 
-    for capture in re.captures_iter(&text) {
-        // Add text before the match to outside_texts
-        if let Some(pre_match) = text.get(last_end..capture.get(0).unwrap().start()) {
-            if !pre_match.trim().is_empty() {
-                result.push(Item {
-                    content: pre_match.trim().to_string(),
-                    content_type: ItemType::Md
-                });
+    // markdown accumulated since the last block-level `{{ }}`
+    let mut md = String::new();
+    let mut md_start = 0;
+    let mut inline_rsx = vec![];
+
+    let push_md = |result: &mut Vec<Item>, md: &mut String, md_start: usize, inline_rsx: &mut Vec<InlineRsx>| {
+        if !md.trim().is_empty() {
+            let trimmed = leading_whitespace(md);
+            for inline in inline_rsx.iter_mut() {
+                inline.placeholder.start -= trimmed;
+                inline.placeholder.end -= trimmed;
             }
+            result.push(Item {
+                content: md.trim().to_string(),
+                content_type: ItemType::Md,
+                offset: md_start + trimmed,
+                inline_rsx: std::mem::take(inline_rsx),
+            });
         }
-        
-        // Add text inside braces as rsx
-        result.push(Item {
-            content: capture[1].to_string(),
-            content_type: ItemType::Rsx,
-        });
-        
-        last_end = capture.get(0).unwrap().end();
-    }
-    
-    // Add any remaining text after last match to outside_texts
-    if last_end < text.len() {
-        if let Some(post_match) = text.get(last_end..) {
-            if !post_match.trim().is_empty() {
-                result.push(Item {
-                    content: post_match.trim().to_string(),
-                    content_type: ItemType::Md
-                });
+        md.clear();
+    };
+
+    for block in find_rsx_blocks(text) {
+        match block {
+            RsxBlockRange::Closed(range) => {
+                md += &text[last_end..range.start];
+                let content = text[range.start + 2..range.end - 2].to_string();
+
+                if is_inline(text, &range) {
+                    // Keep the block inside the markdown text
+                    let placeholder = format!("{PLACEHOLDER_START}{}{PLACEHOLDER_END}", inline_rsx.len());
+                    inline_rsx.push(InlineRsx {
+                        placeholder: md.len()..md.len() + placeholder.len(),
+                        content,
+                        offset: range.start + 2,
+                    });
+                    md += &placeholder;
+                } else {
+                    // Add text before the block as markdown
+                    push_md(&mut result, &mut md, md_start, &mut inline_rsx);
+
+                    // Add text inside braces as rsx
+                    result.push(Item {
+                        content,
+                        content_type: ItemType::Rsx,
+                        offset: range.start + 2,
+                        inline_rsx: vec![],
+                    });
+                    md_start = range.end;
+                }
+
+                last_end = range.end;
+            }
+            RsxBlockRange::Unclosed(start) => {
+                errors.push((
+                    start,
+                    syn::Error::new(Span::call_site(), "unclosed `{{`, expected a matching `}}`"),
+                ));
             }
         }
     }
-    
+
+    // Add any remaining text after last block as markdown
+    md += &text[last_end..];
+    push_md(&mut result, &mut md, md_start, &mut inline_rsx);
+
     result
 }
 
+/// A `{{ }}` block is inline when it is in the middle of a line, with some text on both sides.
+/// At the start or the end of a line, it stays a block of its own.
+fn is_inline(text: &str, block: &Range<usize>) -> bool {
+    let line_start = text[..block.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[block.end..].find('\n').map_or(text.len(), |i| block.end + i);
+    !text[line_start..block.start].trim().is_empty() && !text[block.end..line_end].trim().is_empty()
+}
+
+fn leading_whitespace(text: &str) -> usize {
+    text.len() - text.trim_start().len()
+}
+
 #[derive(Debug, PartialEq)]
 enum ItemType {
     Rsx,
@@ -122,54 +276,248 @@ enum ItemType {
 #[derive(Debug, PartialEq)]
 struct Item {
     content: String,
-    content_type: ItemType
+    content_type: ItemType,
+    /// byte offset of `content` in the page
+    offset: usize,
+    /// `{{ }}` blocks written inside the markdown text, replaced by placeholders in `content`
+    inline_rsx: Vec<InlineRsx>,
+}
+
+#[derive(Debug, PartialEq)]
+struct InlineRsx {
+    /// byte range of the placeholder in the markdown content
+    placeholder: Range<usize>,
+    content: String,
+    /// byte offset of `content` in the page
+    offset: usize,
 }
 
 
 impl Item {
-    fn to_body_nodes(&self) -> Vec<BodyNode> {
+    fn to_body_nodes(
+        &self,
+        options: &ParseOptions,
+        toc: &[PageHeading],
+        errors: &mut Vec<LocatedError>,
+        outline: &mut Outline,
+    ) -> Vec<BodyNode> {
         match self.content_type {
             ItemType::Md => {
-                parse_md(&self.content).expect("malformed md")
+                let inline_nodes = self.inline_rsx
+                    .iter()
+                    .map(|inline| parse_rsx(&inline.content, inline.offset, errors))
+                    .collect();
+                let placeholders: Vec<Range<usize>> = self.inline_rsx
+                    .iter()
+                    .map(|inline| inline.placeholder.clone())
+                    .collect();
+
+                let mut md_errors = vec![];
+                // Anchors are unique in the whole page, so the headings of the previous items are kept
+                let first_link = outline.links.len();
+                let nodes = parse_md(&self.content, inline_nodes, &placeholders, options, toc, &mut md_errors, outline);
+                errors.extend(
+                    md_errors.into_iter().map(|(offset, e)| (self.page_offset(offset), e))
+                );
+                for link in &mut outline.links[first_link..] {
+                    link.offset = self.page_offset(link.offset);
+                }
+                nodes
             }
             ItemType::Rsx => {
-                let block: RsxBlockInner = parse_str(&self.content).expect("unable to parse rust content");
-                block.0.children
+                parse_rsx(&self.content, self.offset, errors)
             }
         }
 
     }
+
+    /// Convert a byte offset in the markdown content into a byte offset in the page
+    fn page_offset(&self, md_offset: usize) -> usize {
+        let (block_len, placeholder_len) = self.inline_rsx
+            .iter()
+            .filter(|inline| inline.placeholder.end <= md_offset)
+            .fold((0, 0), |(block_len, placeholder_len), inline| {
+                // the block was `{{` content `}}`
+                (block_len + inline.content.len() + 4, placeholder_len + inline.placeholder.len())
+            });
+        self.offset + md_offset + block_len - placeholder_len
+    }
+}
+
+fn parse_rsx(content: &str, offset: usize, errors: &mut Vec<LocatedError>) -> Vec<BodyNode> {
+    match parse_str::<RsxBlockInner>(content) {
+        Ok(block) => block.0.children,
+        Err(e) => {
+            let placeholder = error_placeholder(&e);
+            errors.push((offset, e));
+            vec![placeholder]
+        }
+    }
 }
 
 
-fn parse_md(markdown: &str) -> syn::Result<Vec<BodyNode>> {
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TABLES);
-    let mut parser = Parser::new_ext(markdown, options);
+fn parse_md<'a>(
+    markdown: &'a str,
+    inline_nodes: Vec<Vec<BodyNode>>,
+    placeholders: &'a [Range<usize>],
+    options: &'a ParseOptions,
+    toc: &'a [PageHeading],
+    errors: &mut Vec<LocatedError>,
+    outline: &mut Outline,
+) -> Vec<BodyNode> {
+    let parser = Parser::new_ext(markdown, markdown_options());
 
     let mut rsx_parser = RsxMarkdownParser {
         element_stack: vec![],
         root_nodes: vec![],
         current_table: vec![],
         in_table_header: false,
-        iter: parser.by_ref().peekable(),
+        in_table_body: false,
+        current_cell: 0,
+        in_heading_link: false,
+        html_elements: vec![],
+        inline_nodes,
+        placeholders,
+        options,
+        markdown,
+        toc,
+        errors: vec![],
+        outline: std::mem::take(outline),
+        iter: split_placeholders(parser.into_offset_iter(), markdown, placeholders).peekable(),
         phantom: std::marker::PhantomData,
     };
-    rsx_parser.parse()?;
+    rsx_parser.parse();
     while !rsx_parser.element_stack.is_empty() {
         rsx_parser.end_node();
     }
 
-    Ok(rsx_parser.root_nodes)
+    errors.append(&mut rsx_parser.errors);
+    *outline = rsx_parser.outline;
+    rsx_parser.root_nodes
 }
 
-struct RsxMarkdownParser<'a, I: Iterator<Item = Event<'a>>> {
+fn markdown_options() -> Options {
+    let mut markdown_options = Options::empty();
+    markdown_options.insert(Options::ENABLE_STRIKETHROUGH);
+    markdown_options.insert(Options::ENABLE_TABLES);
+    markdown_options.insert(Options::ENABLE_FOOTNOTES);
+    markdown_options.insert(Options::ENABLE_TASKLISTS);
+    markdown_options
+}
+
+/// The text of the heading at `range` of `markdown`, without its formatting and its inline `{{ }}` blocks
+fn heading_text(markdown: &str, range: Range<usize>, placeholders: &[Range<usize>]) -> String {
+    let start = range.start;
+    let events = Parser::new_ext(&markdown[range], markdown_options())
+        .into_offset_iter()
+        .map(|(event, range)| (event, range.start + start..range.end + start));
+    let mut text = String::new();
+    for (event, range) in split_placeholders(events, markdown, placeholders) {
+        match event {
+            Event::Text(part) if inline_block(placeholders, markdown, &part, &range).is_some() => {}
+            Event::Text(part) | Event::Code(part) => text += &part,
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(Tag::Heading(..)) => break,
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Split the text events of `markdown` around the placeholders of its inline `{{ }}` blocks,
+/// so that each placeholder is a text event of its own, with the byte range of the placeholder.
+/// The same characters anywhere else stay text.
+fn split_placeholders<'a>(
+    events: impl Iterator<Item = (Event<'a>, Range<usize>)>,
+    markdown: &'a str,
+    placeholders: &'a [Range<usize>],
+) -> impl Iterator<Item = (Event<'a>, Range<usize>)> {
+    events.flat_map(move |(event, range)| {
+        let Event::Text(text) = &event else {
+            return vec![(event, range)];
+        };
+        let inside = placeholders
+            .iter()
+            .filter(|placeholder| range.start <= placeholder.start && placeholder.end <= range.end);
+        // Text is a slice of the markdown, unless it comes from an entity or an escape
+        let verbatim = markdown.get(range.clone()) == Some(&**text);
+        let mut events = vec![];
+        let mut text_start = 0;
+        for placeholder in inside {
+            let marker = &markdown[placeholder.clone()];
+            let position = if verbatim {
+                Some(placeholder.start - range.start)
+            } else {
+                text[text_start..].find(marker).map(|position| text_start + position)
+            };
+            let Some(position) = position.filter(|position| *position >= text_start) else {
+                continue;
+            };
+            if position > text_start {
+                let part = text[text_start..position].to_string();
+                let part_range = if verbatim { range.start + text_start..range.start + position } else { range.clone() };
+                events.push((Event::Text(part.into()), part_range));
+            }
+            events.push((Event::Text(marker.into()), placeholder.clone()));
+            text_start = position + marker.len();
+        }
+        if events.is_empty() {
+            return vec![(event, range)];
+        }
+        if text_start < text.len() {
+            let part = text[text_start..].to_string();
+            let part_range = if verbatim { range.start + text_start..range.end } else { range.clone() };
+            events.push((Event::Text(part.into()), part_range));
+        }
+        events
+    })
+}
+
+/// The index of the inline `{{ }}` block whose placeholder is the text event `text` at `range`
+fn inline_block(placeholders: &[Range<usize>], markdown: &str, text: &str, range: &Range<usize>) -> Option<usize> {
+    placeholders
+        .iter()
+        .position(|placeholder| placeholder == range && markdown[placeholder.clone()] == *text)
+}
+
+/// The node that replaces a broken region of the page in lenient mode
+fn error_placeholder(error: &syn::Error) -> BodyNode {
+    let message = escape_text(&error.to_string());
+    parse_quote! {
+        pre {
+            class: "markdown-error",
+            #message
+        }
+    }
+}
+
+struct RsxMarkdownParser<'a, I: Iterator<Item = (Event<'a>, Range<usize>)>> {
     element_stack: Vec<BodyNode>,
     root_nodes: Vec<BodyNode>,
 
     current_table: Vec<Alignment>,
     in_table_header: bool,
+    /// Whether the `tbody` of the current table was started
+    in_table_body: bool,
+    /// The column of the next cell of the current row
+    current_cell: usize,
+    /// Whether the current heading is wrapped in a link to itself, which ends with it
+    in_heading_link: bool,
+    /// The elements opened by raw HTML tags, with their depth in the element stack
+    html_elements: Vec<(String, usize)>,
+
+    /// The nodes of the inline `{{ }}` blocks, by placeholder index
+    inline_nodes: Vec<Vec<BodyNode>>,
+    /// The byte ranges of the placeholders of the inline `{{ }}` blocks in the markdown
+    placeholders: &'a [Range<usize>],
+    options: &'a ParseOptions,
+    markdown: &'a str,
+    /// The headings of the whole page, for the `[[toc]]` marker
+    toc: &'a [PageHeading],
+    /// Errors, with their byte offset in the markdown
+    errors: Vec<LocatedError>,
+    /// Headings and links of the page. The offsets of the new links are in the markdown
+    outline: Outline,
 
     iter: Peekable<I>,
 
@@ -177,59 +525,164 @@ struct RsxMarkdownParser<'a, I: Iterator<Item = Event<'a>>> {
 }
 
 
-impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
-    fn parse(&mut self) -> syn::Result<()> {
-        while let Some(event) = self.iter.next() {
-            self.parse_event(event)?;
+impl<'a, I: Iterator<Item = (Event<'a>, Range<usize>)>> RsxMarkdownParser<'a, I> {
+    fn parse(&mut self) {
+        while let Some((event, range)) = self.iter.next() {
+            self.parse_event(event, range);
         }
-        Ok(())
     }
 
-    fn parse_event(&mut self, event: Event) -> syn::Result<()> {
+    fn parse_event(&mut self, event: Event, range: Range<usize>) {
         match event {
             pulldown_cmark::Event::Start(start) => {
-                self.start_element(start)?;
+                self.start_element(start, range);
+            }
+            pulldown_cmark::Event::End(end) => {
+                self.close_html_elements();
+                self.end_element(end);
             }
-            pulldown_cmark::Event::End(_) => self.end_node(),
             pulldown_cmark::Event::Text(text) => {
-                let text = escape_text(&text);
-                self.create_node(BodyNode::Text(parse_quote!(#text)));
+                if let Some(index) = inline_block(self.placeholders, self.markdown, &text, &range) {
+                    self.write_inline_block(index);
+                    return;
+                }
+                let text = self.take_following_text(text.to_string());
+                self.write_text_node(&text, range.start);
             }
-            pulldown_cmark::Event::Code(code) => {
-                let code = escape_text(&code);
-                self.create_node(parse_quote! {
-                    code {
-                        #code
-                    }
-                })
+            pulldown_cmark::Event::Code(code) => self.write_code(&code, range.start),
+            pulldown_cmark::Event::Html(html) => {
+                let mut html = html.to_string();
+                // pulldown_cmark gives each line of an HTML block as a separate event
+                while let Some((pulldown_cmark::Event::Html(next), _)) = self.iter.peek() {
+                    html += next;
+                    let _ = self.iter.next().unwrap();
+                }
+                self.write_html(&html, range.start);
+            }
+            pulldown_cmark::Event::FootnoteReference(label) => {
+                let node = footnote_reference(&mut self.outline.footnotes, &label);
+                self.create_node(node);
+            }
+            pulldown_cmark::Event::SoftBreak => {
+                let text = if self.options.newline_soft_breaks { "\n" } else { " " };
+                self.create_node(BodyNode::Text(parse_quote!(#text)));
             }
-            pulldown_cmark::Event::Html(_) => {}
-            pulldown_cmark::Event::FootnoteReference(_) => {}
-            pulldown_cmark::Event::SoftBreak => {}
-            pulldown_cmark::Event::HardBreak => {}
-            pulldown_cmark::Event::Rule => self.create_node(parse_quote! {
-                hr {}
+            pulldown_cmark::Event::HardBreak => self.create_node(parse_quote! {
+                br {}
             }),
+            pulldown_cmark::Event::Rule => {
+                let node = self.component_node(MarkdownElement::Rule, &[], range.start);
+                self.create_node(node.unwrap_or_else(|| parse_quote! {
+                    hr {}
+                }))
+            }
             pulldown_cmark::Event::TaskListMarker(value) => {
                 self.write_checkbox(value);
             }
         }
-        Ok(())
     }
 
-    fn write_checkbox(&mut self, checked: bool) {
-        let type_value = if checked { "true" } else { "false" };
-        self.create_node(parse_quote! {
-            input {
-                r#type: "checkbox",
-                value: #type_value,
+    fn end_element(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading(..) if self.in_heading_link => {
+                self.in_heading_link = false;
+                self.end_node();
+                self.end_node();
             }
-        })
+            Tag::TableHead => {
+                self.in_table_header = false;
+                self.end_node();
+                self.end_node();
+            }
+            Tag::Table(_) => {
+                if self.in_table_body {
+                    self.in_table_body = false;
+                    self.end_node();
+                }
+                self.end_node();
+            }
+            Tag::FootnoteDefinition(label) => {
+                // The definition goes to the footnotes section instead of its place in the page
+                if let Some(definition) = self.element_stack.pop() {
+                    set_definition(&mut self.outline.footnotes, &label, definition);
+                }
+            }
+            _ => self.end_node(),
+        }
+    }
+
+    fn write_html(&mut self, html: &str, offset: usize) {
+        if self.options.reject_html {
+            self.errors.push((
+                offset,
+                syn::Error::new(Span::call_site(), "raw HTML is not allowed in this page"),
+            ));
+            return;
+        }
+        let Some(tokens) = html_tokens(html) else {
+            // Blocks of HTML end with a line break, HTML in the middle of text doesn't
+            let block = html.ends_with('\n');
+            self.create_node(raw_html(html, block));
+            return;
+        };
+        for token in tokens {
+            match token {
+                HtmlToken::Start { name, attributes, closed } => {
+                    let node = html_element(&name, &attributes);
+                    if closed {
+                        self.create_node(node);
+                    } else {
+                        self.start_node(node);
+                        self.html_elements.push((name, self.element_stack.len()));
+                    }
+                }
+                HtmlToken::End(name) => self.end_html_element(&name),
+                HtmlToken::Text(text) => {
+                    let text = escape_text(&text);
+                    self.create_node(BodyNode::Text(parse_quote!(#text)));
+                }
+            }
+        }
+    }
+
+    /// Close the element opened by the tag `name`, and the elements opened by tags inside it.
+    /// Closing tags without an opening tag in the current markdown element are ignored.
+    fn end_html_element(&mut self, name: &str) {
+        let Some(index) = self.html_elements.iter().rposition(|(open, _)| open == name) else {
+            return;
+        };
+        let depth = self.html_elements[index].1;
+        let count = self.html_elements.len() - index;
+        // A markdown element opened inside it is still open
+        if depth + count - 1 != self.element_stack.len() {
+            return;
+        }
+        self.html_elements.truncate(index);
+        for _ in 0..count {
+            self.end_node();
+        }
+    }
+
+    /// Close the elements of HTML tags left open at the end of the current markdown element
+    fn close_html_elements(&mut self) {
+        while let Some((_, depth)) = self.html_elements.last() {
+            if *depth != self.element_stack.len() {
+                break;
+            }
+            self.html_elements.pop();
+            self.end_node();
+        }
+    }
+
+    fn write_checkbox(&mut self, checked: bool) {
+        let index = self.outline.tasks.len();
+        self.outline.tasks.push(checked);
+        self.create_node(checkbox(&self.options.tasks, index, checked))
     }
 
     fn take_code_or_text(&mut self) -> String {
         let mut current_text = String::new();
-        while let Some(pulldown_cmark::Event::Code(text) | pulldown_cmark::Event::Text(text)) =
+        while let Some((pulldown_cmark::Event::Code(text) | pulldown_cmark::Event::Text(text), _)) =
             self.iter.peek()
         {
             current_text += text;
@@ -241,74 +694,164 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
     fn write_text(&mut self) {
         loop {
             match self.iter.peek() {
-                Some(pulldown_cmark::Event::Text(text)) => {
-                    let mut all_text = text.to_string();
+                Some((pulldown_cmark::Event::Text(text), range)) => {
+                    if let Some(index) = inline_block(self.placeholders, self.markdown, text, range) {
+                        let _ = self.iter.next().unwrap();
+                        self.write_inline_block(index);
+                        continue;
+                    }
+                    let all_text = text.to_string();
+                    let offset = range.start;
 
                     // Take the text or code event we just inserted
                     let _ = self.iter.next().unwrap();
-
-                    // If the next block after this is a code block, insert the space in the text before the code block
-                    if let Some(pulldown_cmark::Event::Code(_)) = self.iter.peek() {
-                        all_text.push(' ');
-                    }
-                    let all_text = escape_text(&all_text);
-
-                    let text = BodyNode::Text(parse_quote!(#all_text));
-                    self.create_node(text);
+                    let all_text = self.take_following_text(all_text);
+                    self.write_text_node(&all_text, offset);
                 }
-                Some(pulldown_cmark::Event::Code(code)) => {
+                Some((pulldown_cmark::Event::Code(code), range)) => {
                     let code = code.to_string();
-                    let code = escape_text(&code);
-                    self.create_node(parse_quote! {
-                        code {
-                            #code
-                        }
-                    });
+                    let offset = range.start;
 
                     // Take the text or code event we just inserted
                     let _ = self.iter.next().unwrap();
+                    self.write_code(&code, offset);
                 }
                 _ => return,
             }
         }
     }
 
+    /// pulldown_cmark splits text on characters that could be markdown syntax, like `*`.
+    /// Join the text events following `text` so that `{=expr}` interpolations stay whole.
+    ///
+    /// At the end of a heading, the `{#id .class}` attributes are removed from the text,
+    /// and the spaces at the end of a line are removed before a line break.
+    fn take_following_text(&mut self, mut text: String) -> String {
+        while let Some((pulldown_cmark::Event::Text(next), range)) = self.iter.peek() {
+            if inline_block(self.placeholders, self.markdown, next, range).is_some() {
+                return text;
+            }
+            text += next;
+            let _ = self.iter.next().unwrap();
+        }
+        match self.iter.peek() {
+            Some((Event::End(Tag::Heading(..)), _)) => {
+                text.truncate(heading_attributes(&text).0.len());
+            }
+            Some((Event::SoftBreak, _)) => text.truncate(text.trim_end_matches(' ').len()),
+            _ => {}
+        }
+        text
+    }
+
+    /// Turn markdown text into an ifmt string, where `{=expr}` becomes `{expr}`, see [`interpolate_text`].
+    /// If an interpolated expression is invalid, the error is recorded and the text is kept literal.
+    fn interpolate(&mut self, text: &str, offset: usize) -> TokenStream {
+        match interpolate_text(text) {
+            Ok(interpolated) => interpolated,
+            Err(e) => {
+                self.errors.push((offset, e));
+                let text = escape_text(text);
+                quote!(#text)
+            }
+        }
+    }
+
+    fn write_text_node(&mut self, text: &str, offset: usize) {
+        if !text.is_empty() {
+            let text = self.interpolate(text, offset);
+            self.create_node(parse_quote!(#text));
+        }
+    }
+
+    /// Write the nodes of the inline `{{ }}` block in place of its placeholder
+    fn write_inline_block(&mut self, index: usize) {
+        for node in std::mem::take(&mut self.inline_nodes[index]) {
+            self.create_node(node);
+        }
+    }
+
     fn take_text(&mut self) -> String {
         let mut current_text = String::new();
-        // pulldown_cmark will create a new text node for each newline. We insert a space
-        // between each newline to avoid two lines being rendered right next to each other.
-        let mut insert_space = false;
-        while let Some(pulldown_cmark::Event::Text(text)) = self.iter.peek() {
-            if insert_space {
-                current_text.push(' ');
+        loop {
+            match self.iter.peek() {
+                // Inline `{{ }}` blocks have no text
+                Some((pulldown_cmark::Event::Text(text), range))
+                    if inline_block(self.placeholders, self.markdown, text, range).is_some() => {}
+                Some((pulldown_cmark::Event::Text(text), _)) => current_text += text,
+                // Line breaks separate the lines with a space
+                Some((pulldown_cmark::Event::SoftBreak | pulldown_cmark::Event::HardBreak, _)) => {
+                    current_text.push(' ')
+                }
+                _ => return current_text,
             }
-            current_text += text;
-            insert_space = true;
             let _ = self.iter.next().unwrap();
         }
-        current_text
     }
 
-    fn start_element(&mut self, tag: Tag) -> syn::Result<()> {
+    /// The node of `element` when it is rendered by a component, or `None` to render it as html.
+    /// A broken component call is recorded as an error and replaced with a placeholder.
+    fn component_node(
+        &mut self,
+        element: MarkdownElement,
+        attributes: &[(&str, TokenStream)],
+        offset: usize,
+    ) -> Option<BodyNode> {
+        let component = self.options.components.get(element)?;
+        match override_node(component, attributes) {
+            Ok(node) => Some(node),
+            Err(e) => {
+                let placeholder = error_placeholder(&e);
+                self.errors.push((offset, e));
+                Some(placeholder)
+            }
+        }
+    }
+
+    fn write_code(&mut self, code: &str, offset: usize) {
+        let code = escape_text(code);
+        if let Some(node) = self.component_node(MarkdownElement::Code, &[("code", quote!(#code))], offset) {
+            self.create_node(node);
+            return;
+        }
+        self.create_node(parse_quote! {
+            code {
+                #code
+            }
+        })
+    }
+
+    fn start_element(&mut self, tag: Tag, range: Range<usize>) {
         match tag {
+            Tag::Paragraph if self.markdown[range.clone()].trim() == TOC_MARKER => {
+                self.outline.has_toc = true;
+                // Skip the text of the marker
+                for (event, _) in self.iter.by_ref() {
+                    if let Event::End(Tag::Paragraph) = event {
+                        break;
+                    }
+                }
+                self.create_node(toc_list(&toc_tree(self.toc)));
+            }
             Tag::Paragraph => {
-                self.start_node(parse_quote! {
+                let node = self.component_node(MarkdownElement::Paragraph, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
                     p {}
-                });
+                }));
                 self.write_text();
             }
             Tag::Heading(level, _, _) => {
-                let text = self.take_text();
-                let anchor: String = text
-                    .trim()
-                    .to_lowercase()
-                    .chars()
-                    .filter_map(|char| match char {
-                        '-' | 'a'..='z' | '0'..='9' => Some(char),
-                        ' ' | '_' => Some('-'),
-                        _ => None,
-                    })
-                    .collect();
+                let text = heading_text(self.markdown, range.clone(), self.placeholders);
+                let (text, id, classes) = heading_attributes(&text);
+                let anchor = match id {
+                    Some(id) => id.to_string(),
+                    None => unique_slug(&slug(text), &self.outline.headings),
+                };
+                self.outline.headings.push(PageHeading {
+                    level: level as u8,
+                    text: text.to_string(),
+                    anchor: anchor.clone(),
+                });
                 let fragment = format!("#{}", anchor);
                 let element_name = match level {
                     pulldown_cmark::HeadingLevel::H1 => Ident::new("h1", Span::call_site()),
@@ -320,57 +863,111 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                 };
                 let anchor = escape_text(&anchor);
                 let fragment = escape_text(&fragment);
-                let text = escape_text(&text);
-                let element = parse_quote! {
+
+                let level = Literal::usize_unsuffixed(level as usize);
+                let attributes = [("level", quote!(#level)), ("id", quote!(#anchor))];
+                if let Some(node) = self.component_node(MarkdownElement::Heading, &attributes, range.start) {
+                    self.start_node(node);
+                    self.write_text();
+                    return;
+                }
+
+                let class = (!classes.is_empty()).then(|| {
+                    let class = escape_text(&classes.join(" "));
+                    quote!(class: #class,)
+                });
+                self.start_node(parse_quote! {
                     #element_name {
                         id: #anchor,
+                        #class
+                    }
+                });
+                if !self.options.plain_headings {
+                    // The formatted content of the heading goes inside the link
+                    self.in_heading_link = true;
+                    self.start_node(parse_quote! {
                         a {
                             href: #fragment,
                             class: "header",
-                            #text
                         }
-                    }
-                };
-                self.start_node(element);
+                    });
+                }
+                self.write_text();
             }
             Tag::BlockQuote => {
-                self.start_node(parse_quote! {
+                let node = self.component_node(MarkdownElement::BlockQuote, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
                     blockquote {}
-                });
+                }));
                 self.write_text();
             }
             Tag::CodeBlock(kind) => {
-                let lang = match kind {
-                    pulldown_cmark::CodeBlockKind::Indented => None,
-                    pulldown_cmark::CodeBlockKind::Fenced(lang) => {
-                        (!lang.is_empty()).then_some(lang)
-                    }
+                let fence = match kind {
+                    pulldown_cmark::CodeBlockKind::Indented => Ok(Default::default()),
+                    pulldown_cmark::CodeBlockKind::Fenced(info) => fence_info(&info),
                 };
+                let code_offset = self.iter.peek()
+                    .map_or(range.start, |(_, code_range)| code_range.start);
                 let raw_code = self.take_code_or_text();
+                let fence = match fence {
+                    Ok(fence) => fence,
+                    Err(message) => {
+                        self.start_error(range.start, syn::Error::new(Span::call_site(), message));
+                        return;
+                    }
+                };
+                let lang = fence.lang.as_deref();
 
-                if lang.as_deref() == Some("inject-dioxus") {
-                    self.start_node(parse_str::<BodyNode>(&raw_code).unwrap());
+                if lang == Some("inject-dioxus") {
+                    match parse_str::<BodyNode>(&raw_code) {
+                        Ok(node) => self.start_node(node),
+                        Err(e) => self.start_error(code_offset, e),
+                    }
                 } else {
-                    let code = transform_code_block(raw_code)?;
+                    let code = match transform_code_block(raw_code) {
+                        Ok(code) => code,
+                        Err(errors) => {
+                            let mut errors = errors.into_iter();
+                            let (first_offset, first) = errors.next().unwrap();
+                            self.start_error(code_offset + first_offset, first);
+                            self.errors.extend(errors.map(|(offset, e)| (code_offset + offset, e)));
+                            return;
+                        }
+                    };
 
-                    let ss = SyntaxSet::load_defaults_newlines();
-                    let ts = ThemeSet::load_defaults();
+                    let source = escape_text(&code);
+                    let lang_name = escape_text(lang.unwrap_or_default());
+                    let title = escape_text(fence.title.as_deref().unwrap_or_default());
+                    let attributes = [("code", quote!(#source)), ("lang", quote!(#lang_name)), ("title", quote!(#title))];
+                    if let Some(node) = self.component_node(MarkdownElement::CodeBlock, &attributes, range.start) {
+                        self.start_node(node);
+                        return;
+                    }
 
-                    let theme = &ts.themes["base16-ocean.dark"];
-                    let syntax = ss.find_syntax_by_extension("rs").unwrap();
-                    let html = escape_text(
-                        &syntect::html::highlighted_html_for_string(&code, &ss, syntax, theme)
-                            .unwrap(),
-                    );
+                    let html = match highlight(&self.options.highlight, &code, &fence) {
+                        Ok(html) => escape_text(&html),
+                        Err(message) => {
+                            self.start_error(range.start, syn::Error::new(Span::call_site(), message));
+                            return;
+                        }
+                    };
+                    let data_lang = lang.map(|lang| {
+                        let lang = escape_text(lang);
+                        quote!("data-lang": #lang,)
+                    });
+                    let title = fence.title.is_some().then(|| quote!(div { class: "hl-title", #title }));
                     self.start_node(parse_quote!{
                         div {
                             style: "position: relative;",
+                            #data_lang
+                            #title
                             div {
                                 dangerous_inner_html: #html
                             }
                             button {
                                 style: "position: absolute; top: 0; right: 0; background: rgba(0, 0, 0, 0.75); color: white; border: 1px solid white; padding: 0.25em;",
-                                "onclick": "navigator.clipboard.writeText(this.previousElementSibling.innerText)",
+                                // Line numbers are not part of the code
+                                "onclick": "const code = this.previousElementSibling.cloneNode(true); code.querySelectorAll('.hl-lineno').forEach(number => number.remove()); navigator.clipboard.writeText(code.textContent)",
                                 "Copy"
                             }
                         }
@@ -378,6 +975,13 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                 }
             }
             Tag::List(first) => {
+                let ordered = first.is_some();
+                let start = Literal::u64_unsuffixed(first.unwrap_or(1));
+                let attributes = [("ordered", quote!(#ordered)), ("start", quote!(#start))];
+                if let Some(node) = self.component_node(MarkdownElement::List, &attributes, range.start) {
+                    self.start_node(node);
+                    return;
+                }
                 let name = match first {
                     Some(_) => Ident::new("ol", Span::call_site()),
                     None => Ident::new("ul", Span::call_site()),
@@ -386,57 +990,100 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                     #name {}
                 })
             }
-            Tag::Item => self.start_node(parse_quote! {
-                li {}
-            }),
-            Tag::FootnoteDefinition(_) => {}
+            Tag::Item => {
+                let node = self.component_node(MarkdownElement::Item, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
+                    li {}
+                }))
+            }
+            Tag::FootnoteDefinition(_) => self.start_node(footnote_item()),
             Tag::Table(alignments) => {
                 self.current_table = alignments;
-                self.start_node(parse_quote! {
+                let node = self.component_node(MarkdownElement::Table, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
                     table {}
-                })
+                }))
             }
             Tag::TableHead => {
                 self.in_table_header = true;
+                self.current_cell = 0;
+                // pulldown_cmark has no row in the head of a table
                 self.start_node(parse_quote! {
                     thead {}
+                });
+                self.start_node(parse_quote! {
+                    tr {}
+                })
+            }
+            Tag::TableRow => {
+                if !self.in_table_body {
+                    self.in_table_body = true;
+                    self.start_node(parse_quote! {
+                        tbody {}
+                    });
+                }
+                self.current_cell = 0;
+                self.start_node(parse_quote! {
+                    tr {}
                 })
             }
-            Tag::TableRow => self.start_node(parse_quote! {
-                tr {}
-            }),
             Tag::TableCell => {
                 let name = if self.in_table_header { "th" } else { "td" };
                 let ident = Ident::new(name, Span::call_site());
+                let alignment = match self.current_table.get(self.current_cell) {
+                    Some(Alignment::Left) => Some("text-align: left"),
+                    Some(Alignment::Center) => Some("text-align: center"),
+                    Some(Alignment::Right) => Some("text-align: right"),
+                    Some(Alignment::None) | None => None,
+                };
+                self.current_cell += 1;
+                let style = alignment.map(|alignment| quote!(style: #alignment,));
                 self.start_node(parse_quote! {
-                    #ident {}
+                    #ident { #style }
                 })
             }
-            Tag::Emphasis => self.start_node(parse_quote! {
-                em {}
-            }),
-            Tag::Strong => self.start_node(parse_quote! {
-                strong {}
-            }),
-            Tag::Strikethrough => self.start_node(parse_quote! {
-                s {}
-            }),
+            Tag::Emphasis => {
+                let node = self.component_node(MarkdownElement::Emphasis, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
+                    em {}
+                }))
+            }
+            Tag::Strong => {
+                let node = self.component_node(MarkdownElement::Strong, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
+                    strong {}
+                }))
+            }
+            Tag::Strikethrough => {
+                let node = self.component_node(MarkdownElement::Strikethrough, &[], range.start);
+                self.start_node(node.unwrap_or_else(|| parse_quote! {
+                    s {}
+                }))
+            }
             Tag::Link(ty, dest, title) => {
-                let without_extension = dest.trim_end_matches(".md");
-                let without_index = without_extension.trim_end_matches("/index");
-
-                let href = match ty {
-                    pulldown_cmark::LinkType::Email => format!("mailto:{}", without_index),
-                    _ => {
-                        if dest.starts_with("http") || dest.starts_with("https") {
-                            dest.to_string()
-                        } else {
-                            without_index.to_string()
-                        }
+                self.outline.links.push(PageLink {
+                    dest: dest.to_string(),
+                    offset: range.start,
+                });
+                let target = self
+                    .options
+                    .links
+                    .target(&dest, ty == pulldown_cmark::LinkType::Email);
+                let href = match &target {
+                    LinkTarget::Route(route) => route.clone(),
+                    LinkTarget::Anchor(href) | LinkTarget::Router(href) | LinkTarget::External(href) => {
+                        let href = self.interpolate(href, range.start);
+                        quote!(#href)
                     }
                 };
-                let href = escape_text(&href);
                 let title = escape_text(&title);
+
+                let attributes = [("href", href.clone()), ("title", quote!(#title))];
+                if let Some(node) = self.component_node(MarkdownElement::Link, &attributes, range.start) {
+                    self.start_node(node);
+                    self.write_text();
+                    return;
+                }
                 let title_attr = if !title.is_empty() {
                     quote! {
                         title: #title,
@@ -445,21 +1092,53 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                     quote! {}
                 };
 
-                self.start_node(parse_quote! {
-                    a {
-                        href: #href,
-                        #title_attr
-                        #title
+                let node = match target {
+                    LinkTarget::Route(_) | LinkTarget::Router(_) => parse_quote! {
+                        Link {
+                            to: #href,
+                            #title_attr
+                        }
+                    },
+                    LinkTarget::Anchor(_) => parse_quote! {
+                        a {
+                            href: #href,
+                            #title_attr
+                        }
+                    },
+                    LinkTarget::External(_) => {
+                        let links = &self.options.links;
+                        let rel = links.external_rel.as_ref().map(|rel| {
+                            let rel = escape_text(rel);
+                            quote!(rel: #rel,)
+                        });
+                        let new_tab = links.external_new_tab.then(|| quote!(target: "_blank",));
+                        parse_quote! {
+                            a {
+                                href: #href,
+                                #title_attr
+                                #rel
+                                #new_tab
+                            }
+                        }
                     }
-                });
+                };
+                self.start_node(node);
 
                 self.write_text();
             }
             Tag::Image(_, dest, title) => {
-                let alt = escape_text(&self.take_text());
+                let alt = self.take_text();
+                let alt = self.interpolate(&alt, range.start);
                 let dest: &str = &dest;
                 let title = escape_text(&title);
 
+                let src = escape_text(dest);
+                let attributes = [("src", quote!(#src)), ("alt", quote!(#alt)), ("title", quote!(#title))];
+                if let Some(node) = self.component_node(MarkdownElement::Image, &attributes, range.start) {
+                    self.start_node(node);
+                    return;
+                }
+
                 #[cfg(not(feature = "manganis"))]
                 let url: syn::Expr = {
                     let dest = escape_text(dest);
@@ -485,19 +1164,32 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                 })
             }
         }
-        Ok(())
     }
 
     fn start_node(&mut self, node: BodyNode) {
         self.element_stack.push(node);
     }
 
+    /// Record an error, and start a placeholder node in place of the broken element
+    fn start_error(&mut self, offset: usize, error: syn::Error) {
+        self.start_node(error_placeholder(&error));
+        self.errors.push((offset, error));
+    }
+
     fn end_node(&mut self) {
-        if let Some(node) = self.element_stack.pop() {
+        if let Some(mut node) = self.element_stack.pop() {
+            if let BodyNode::Component(component) = &mut node {
+                // Now that all the children are known, build their template
+                let children = std::mem::take(&mut component.children.roots);
+                component.children = TemplateBody::new(children);
+            }
             match self.last_mut() {
                 Some(BodyNode::Element(element)) => {
                     element.children.push(node);
                 }
+                Some(BodyNode::Component(component)) => {
+                    component.children.roots.push(node);
+                }
                 None => {
                     self.root_nodes.push(node);
                 }
@@ -510,6 +1202,7 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
         // Find the list of elements we should add the node to
         let element_list = match self.last_mut() {
             Some(BodyNode::Element(element)) => &mut element.children,
+            Some(BodyNode::Component(component)) => &mut component.children.roots,
             None => &mut self.root_nodes,
             _ => return,
         };
@@ -532,20 +1225,32 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
     }
 }
 
-fn transform_code_block(code_contents: String) -> syn::Result<String> {
-    let segments = code_contents.split("{{#");
-    let mut output = String::new();
+/// Resolve the `{{#extension}}`s of a code block.
+///
+/// On failure, returns every broken extension along with its byte offset in the code.
+fn transform_code_block(code_contents: String) -> Result<String, Vec<LocatedError>> {
+    let mut segments = code_contents.split("{{#");
+    let mut output = segments.next().unwrap_or_default().to_string();
+    let mut offset = output.len();
+    let mut errors = vec![];
     for segment in segments {
         if let Some((plugin, after)) = segment.split_once("}}") {
-            if plugin.starts_with("include") {
-                output += &resolve_extension(plugin)?;
-                output += after;
+            match resolve_extension(plugin) {
+                Ok(resolved) => output += &resolved,
+                Err(e) => errors.push((offset, e)),
             }
+            output += after;
         } else {
+            output += "{{#";
             output += segment;
         }
+        offset += "{{#".len() + segment.len();
+    }
+    if errors.is_empty() {
+        Ok(output)
+    } else {
+        Err(errors)
     }
-    Ok(output)
 }
 
 fn resolve_extension(ext: &str) -> syn::Result<String> {
@@ -600,7 +1305,10 @@ fn resolve_extension(ext: &str) -> syn::Result<String> {
             Ok(result)
         }
     } else {
-        todo!("Unknown extension: {}", ext);
+        Err(syn::Error::new(
+            Span::call_site(),
+            format!("Unknown extension: {}", ext.trim()),
+        ))
     }
 }
 
@@ -608,6 +1316,38 @@ fn escape_text(text: &str) -> String {
     text.replace('{', "{{").replace('}', "}}")
 }
 
+/// Like [`escape_text`], but `{=expr}` becomes the `{expr}` placeholder of an ifmt string.
+///
+/// The braces of an expression are balanced, like in `{=if done { "yes" } else { "no" }}`.
+/// An ifmt string can't hold such an expression: the text becomes a `format!` block then,
+/// which is both a node and an attribute value in rsx.
+fn interpolate_text(text: &str) -> syn::Result<TokenStream> {
+    let mut source = String::new();
+    let mut format = String::new();
+    let mut exprs: Vec<syn::Expr> = vec![];
+    let mut braced = false;
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once("{=") {
+        let Some(end) = expression_end(after, 0) else {
+            break;
+        };
+        let expr = &after[..end - 1];
+        source += &format!("{}{{{expr}}}", escape_text(before));
+        format += &format!("{}{{}}", escape_text(before));
+        braced |= expr.contains(['{', '}']);
+        exprs.push(syn::parse_str(expr)?);
+        rest = &after[end..];
+    }
+    source += &escape_text(rest);
+    format += &escape_text(rest);
+
+    if braced {
+        return Ok(quote!({ ::std::format!(#format, #(#exprs),*) }));
+    }
+    syn::parse2::<IfmtInput>(quote!(#source))?;
+    Ok(quote!(#source))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -615,25 +1355,121 @@ mod tests {
     #[test]
     fn test_parse(){
         let content = "{{}} after";
-        let items = extract_items(content);
+        let items = extract_items(content, &mut vec![]);
         assert_eq!(
             items,
-            vec![Item {content: "".to_string(), content_type: ItemType::Rsx}, Item { content: "after".to_string(), content_type: ItemType::Md }]
+            vec![Item {content: "".to_string(), content_type: ItemType::Rsx, offset: 2, inline_rsx: vec![]}, Item { content: "after".to_string(), content_type: ItemType::Md, offset: 5, inline_rsx: vec![] }]
         );
     }
     #[test]
     fn test_parse_multiple(){
         let content = "a {{}}";
-        let items = extract_items(content);
+        let items = extract_items(content, &mut vec![]);
         assert_eq!(
             items,
             vec![
-            Item { content: "a".to_string(), content_type: ItemType::Md},
-            Item { content: "".to_string(), content_type: ItemType::Rsx},
+            Item { content: "a".to_string(), content_type: ItemType::Md, offset: 0, inline_rsx: vec![]},
+            Item { content: "".to_string(), content_type: ItemType::Rsx, offset: 4, inline_rsx: vec![]},
             ]
         );
     }
     #[test]
+    fn test_parse_inline(){
+        let content = "Hello {{ Greet { name: \"x\" } }} and welcome";
+        let items = extract_items(content, &mut vec![]);
+        assert_eq!(
+            items,
+            vec![Item {
+                content: "Hello \u{E000}0\u{E001} and welcome".to_string(),
+                content_type: ItemType::Md,
+                offset: 0,
+                inline_rsx: vec![InlineRsx {
+                    placeholder: 6..13,
+                    content: " Greet { name: \"x\" } ".to_string(),
+                    offset: 8,
+                }],
+            }]
+        );
+
+        let body = parse(content).unwrap();
+        let expected: CallBody = parse_quote! {
+            p {
+                "Hello "
+                Greet { name: "x" }
+                " and welcome"
+            }
+        };
+        assert_eq!(body.body.roots, expected.body.roots);
+    }
+    #[test]
+    fn test_inline_placeholders(){
+        // Private use characters of the page stay text, even when they look like a placeholder
+        let content = "Icon \u{E000} and \u{E000}0\u{E001} then {{ b {} }} end \u{E001}";
+        let body = parse(content).unwrap();
+        let expected: CallBody = parse_quote! {
+            p {
+                "Icon \u{e000} and \u{e000}0\u{e001} then "
+                b {}
+                " end \u{e001}"
+            }
+        };
+        assert_eq!(body.body.roots, expected.body.roots);
+
+        // Inline blocks have no text in headings, the table of contents and alt text
+        let content = "# Hello {{ b {} }} world\n\n[[toc]]\n\n![an {{ b {} }} image](a.png)";
+        let page = parse_page(content, &ParseOptions::default()).unwrap();
+        assert_eq!(page.headings[0].text, "Hello  world");
+        let body = page.body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains(r#"text : "Hello  world""#));
+        assert!(body.contains(r#"value : "an  image""#));
+        assert!(!body.contains('\u{E000}') && !body.to_lowercase().contains("u{e000}"));
+    }
+    #[test]
+    fn test_interpolation(){
+        let content = "# Hi {=name}\n\nWelcome back, {=name}! {=count * 2} {not this}\n\n[me](/users/{=id}) `{=code}`";
+        let body = parse(content).unwrap();
+        let expected: CallBody = parse_quote! {
+            h1 {
+                id: "hi-name",
+                a { href: "#hi-name", class: "header", "Hi {name}" }
+            }
+            p { "Welcome back, {name}! {count * 2} {{not this}}" }
+            p {
+                a { href: "/users/{id}", "me" }
+                " "
+                code { "{{=code}}" }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        // Braces inside the expression are balanced, and the ones in strings are ignored
+        let body = parse("Done: {=if done { \"yes\" } else { \"no\" }} {=\"}\"} {=a}").unwrap();
+        let expected: CallBody = parse_quote! {
+            p {
+                {
+                    ::std::format!(
+                        "Done: {} {} {}",
+                        if done { "yes" } else { "no" },
+                        "}",
+                        a
+                    )
+                }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        let errors = parse_page("text {=1 +}", &ParseOptions::default()).unwrap_err();
+        assert_eq!(errors[0].column, 1);
+    }
+    #[test]
+    fn test_inline_error_location(){
+        let content = "a {{ b }} c\n\n```inject-dioxus\ndiv {\n```\n";
+        let errors = parse_page(content, &ParseOptions::default()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 4);
+    }
+    #[test]
     fn test_macro(){
         let content = r#"
         stuff
@@ -647,6 +1483,648 @@ mod tests {
 
         ";
         "#;
-        let _items = extract_items(content);
+        let _items = extract_items(content, &mut vec![]);
+    }
+    #[test]
+    fn test_tight_braces_and_format_strings(){
+        let content = "a\n{{ div { span { \"{{x}}\" }} }}\nb";
+        let items = extract_items(content, &mut vec![]);
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].content, " div { span { \"{{x}}\" }} ");
+        assert_eq!(items[2].content, "b");
+    }
+    #[test]
+    fn test_no_blocks_in_markdown_code(){
+        let content = "use `{{ }}` or \\{{ this }}\n\n```md\n{{\n```\n\n~~~~\n}} ```\n~~~~\n";
+        let mut errors = vec![];
+        let items = extract_items(content, &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].content_type, ItemType::Md);
+
+        // An unclosed code span ends with its paragraph, and a fence is indented by 3 spaces at most
+        for content in ["a `b\n\n{{ div {} }}\n\nc`", "    ```\n\n{{ div {} }}\n"] {
+            let items = extract_items(content, &mut errors);
+            assert!(errors.is_empty());
+            assert_eq!(items[1].content_type, ItemType::Rsx);
+        }
+    }
+    #[test]
+    fn test_code_block_extension_errors(){
+        let content = "```rust\nfn main() {}\n{{#include missing.rs}}\n{{#unknown}}\n```\n";
+        let errors = parse_page(content, &ParseOptions::default()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[0].column), (3, 1));
+        assert_eq!(errors[1].message, "Unknown extension: unknown");
+        assert_eq!(errors[1].line, 4);
+    }
+    #[test]
+    fn test_unclosed_block(){
+        let mut errors = vec![];
+        let items = extract_items("text {{ div { \"}}\" }", &mut errors);
+        assert_eq!(items.len(), 1);
+        assert_eq!(errors[0].0, 5);
+    }
+    #[test]
+    fn test_rsx_error_location(){
+        let content = "# Title\n\n{{\n    Greet {\n        name: \"x\",,\n    }\n}}\n";
+        let error = parse(content).unwrap_err();
+        assert_eq!(error.line, 5);
+        assert_eq!(error.column, 19);
+        assert_eq!(error.snippet, "        name: \"x\",,");
+    }
+    #[test]
+    fn test_inject_dioxus_error_location(){
+        let content = "text\n\n```inject-dioxus\ndiv { \"a\" \"b\n```\n";
+        let error = parse(content).unwrap_err().with_file("page.md");
+        assert_eq!(error.line, 4);
+        assert!(error.to_string().contains("--> page.md:4:"));
+    }
+    #[test]
+    fn test_component_overrides(){
+        let options = ParseOptions::default()
+            .with_component(MarkdownElement::Link, quote!(Link { to: {href} }))
+            .unwrap()
+            .with_component(MarkdownElement::Heading, quote!(Heading { level: {level}, id: {id} }))
+            .unwrap();
+        let content = "## Intro\n\nSee [the *docs*](/docs/{=page}.md) here";
+        let page = parse_page(content, &options).unwrap();
+        let expected: CallBody = parse_quote! {
+            Heading { level: 2, id: "intro", "Intro" }
+            p {
+                "See "
+                Link { to: "/docs/{page}", "the " em { "docs" } }
+                " here"
+            }
+        };
+        let body = page.body;
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        let map: ComponentMap = parse_str("blockquote => Callout { kind: \"note\" }, code => Code {}").unwrap();
+        assert!(map.get(MarkdownElement::BlockQuote).is_some());
+        assert!(parse_str::<ComponentMap>("quote => Callout {}").is_err());
+    }
+    #[test]
+    fn test_route_links(){
+        let options: ParseOptions = parse_str(r#"
+            base: "/docs/page",
+            routes: { "/perfect_clear" => Route::PerfectClearPage {} },
+            external_rel: "noopener",
+        "#).unwrap();
+        let content = "[a](guide/index.md#intro \"Guide\") [b](/perfect_clear) [c](#here) [d](../x.md) [e](https://dioxuslabs.com) <me@example.com>";
+        let body = parse_page(content, &options).unwrap().body;
+        let expected: CallBody = parse_quote! {
+            p {
+                Link { to: "/docs/guide#intro", title: "Guide", "a" }
+                " "
+                Link { to: Route::PerfectClearPage {}, "b" }
+                " "
+                a { href: "#here", "c" }
+                " "
+                Link { to: "/x", "d" }
+                " "
+                a { href: "https://dioxuslabs.com", rel: "noopener", "e" }
+                " "
+                a { href: "mailto:me@example.com", rel: "noopener", "me@example.com" }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        // Without the router, links to pages stay anchors
+        let body = parse("[a](guide/index.md)").unwrap();
+        let expected: CallBody = parse_quote! { p { a { href: "guide", "a" } } };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        // The root index is the root of the site, or the directory of the base
+        let body = parse("[home](index.md)").unwrap();
+        let expected: CallBody = parse_quote! { p { a { href: "/", "home" } } };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+        let body = parse_page("[home](index.md)", &options).unwrap().body;
+        let expected: CallBody = parse_quote! { p { Link { to: "/docs", "home" } } };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+    }
+    #[test]
+    fn test_broken_links(){
+        let options = ParseOptions::default();
+        let mut index = PageIndex::default();
+        index.insert("docs/guide/setup.md", &parse_page("# Install\n\n## Setup", &options).unwrap());
+
+        let content = "# Intro\n\n[a](guide/setup.md#setup) [b](#intro) [c](https://x.dev/a.md)\n\n[d](./guide/../guide/setup.md#nope) [e](missing.md)\n\n[f](#outro) [g](/perfect_clear)";
+        let page = parse_page(content, &options).unwrap();
+        let errors = index.check("docs/index.md", content, &page);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].message, "broken link: no heading `#nope` in `./guide/../guide/setup.md`");
+        assert_eq!((errors[0].line, errors[0].column), (5, 1));
+        assert_eq!(errors[1].message, "broken link: `missing.md` does not exist");
+        assert_eq!((errors[1].line, errors[1].column), (5, 37));
+        assert_eq!(errors[2].message, "broken link: no heading `#outro` in this page");
+        assert!(errors[2].to_string().contains("--> docs/index.md:7:1"));
+        // Inserted and missing pages are not read from the disk
+        assert!(index.read_pages().is_empty());
+
+        let dir = std::env::temp_dir().join(format!("parse-markdown-links-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("other.md"), "# Other").unwrap();
+        let content = "[o](other.md#other)";
+        let mut index = PageIndex::default();
+        assert!(index.check(dir.join("index.md"), content, &parse_page(content, &options).unwrap()).is_empty());
+        assert_eq!(index.read_pages(), [dir.join("other.md")]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn test_front_matter(){
+        let content = "---\ntitle: Hello\ntags: [a, b]\nauthor: me\n---\n\n# Hello\n\n---\n\n{{ div { , } }}";
+        let errors = parse_page(content, &ParseOptions::default()).unwrap_err();
+        // offsets after the front matter are still offsets in the page
+        assert_eq!(errors[0].line, 11);
+
+        let page = parse_page(&content.replace("{ , }", "{}"), &ParseOptions::default()).unwrap();
+        assert_eq!(page.meta.title.as_deref(), Some("Hello"));
+        assert_eq!(page.meta.tags, ["a", "b"]);
+        assert_eq!(page.meta.extra, [("author".to_string(), "me".to_string())]);
+        // the front matter is not rendered, the thematic break is
+        let roots = &page.body.body.roots;
+        assert_eq!(roots.len(), 3);
+        assert!(matches!(&roots[1], BodyNode::Element(hr) if hr.name == "hr"));
+
+        let toml = "+++\ntitle = \"Hello\" # comment\ntags = [\"a\", 'b']\n[extra]\ndraft = true\n+++\ntext";
+        let meta = parse_front_matter(toml).unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Hello"));
+        assert_eq!(meta.tags, ["a", "b"]);
+        assert_eq!(meta.extra, [("extra.draft".to_string(), "true".to_string())]);
+
+        let errors = parse_front_matter("---\ntitle: ok\ndate: x: y\n---\n").unwrap_err();
+        assert_eq!((errors[0].line, errors[0].column), (3, 8));
+        let errors = parse_front_matter("+++\ntitle = \"a\"\nbroken\n+++\n").unwrap_err();
+        assert!(errors[0].message.starts_with("invalid front matter: "));
+        assert_eq!(errors[0].line, 3);
+        let errors = parse_front_matter("+++\ntitle = [\"a\"]\n+++\n").unwrap_err();
+        assert_eq!(errors[0].message, "`title` must be a single value, not a list");
+
+        // A block that is not a list of `key: value` is markdown: a thematic break and a heading
+        let page = parse_page("---\nJust a paragraph\n---\n", &ParseOptions::default()).unwrap();
+        assert_eq!(page.meta, FrontMatter::default());
+        assert!(matches!(&page.body.body.roots[0], BodyNode::Element(hr) if hr.name == "hr"));
+        assert_eq!(page.headings[0].text, "Just a paragraph");
+    }
+    #[test]
+    fn test_table_of_contents(){
+        let content = "[[toc]]\n\n# Guide\n\n### Install\n\n{{ div {} }}\n\n## Use\n\n# End\n\n`[[toc]]`";
+        let page = parse_page(content, &ParseOptions::default()).unwrap();
+        let levels: Vec<u8> = page.headings.iter().map(|heading| heading.level).collect();
+        assert_eq!(levels, [1, 3, 2, 1]);
+
+        let tree = toc_tree(&page.headings);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[1].heading.anchor, "use");
+
+        let toc = &page.body.body.roots[0];
+        let expected: BodyNode = parse_quote! {
+            ul {
+                class: "toc",
+                li {
+                    a { href: "#guide", "Guide" }
+                    ul {
+                        li { a { href: "#install", "Install" } }
+                        li { a { href: "#use", "Use" } }
+                    }
+                }
+                li { a { href: "#end", "End" } }
+            }
+        };
+        assert_eq!(quote!(#toc).to_string(), quote!(#expected).to_string());
+        // only a paragraph containing the marker alone is replaced
+        let last = page.body.body.roots.last().unwrap();
+        assert!(quote!(#last).to_string().contains("[[toc]]"));
+    }
+    #[test]
+    fn test_heading_anchors(){
+        let content = "# Setup\n\n{{ div {} }}\n\n## Setup\n\n## Setup!\n\n## Café 日本語\n\n## Install {#custom .wide}";
+        let page = parse_page(content, &ParseOptions::default()).unwrap();
+        let anchors: Vec<&str> = page.headings.iter().map(|heading| heading.anchor.as_str()).collect();
+        assert_eq!(anchors, ["setup", "setup-1", "setup-2", "café-日本語", "custom"]);
+        assert_eq!(page.headings[4].text, "Install");
+
+        let last = page.body.body.roots.last().unwrap();
+        let expected: BodyNode = parse_quote! {
+            h2 {
+                id: "custom",
+                class: "wide",
+                a { href: "#custom", class: "header", "Install" }
+            }
+        };
+        assert_eq!(quote!(#last).to_string(), quote!(#expected).to_string());
+
+        let plain = ParseOptions { plain_headings: true, ..Default::default() };
+        let body = parse_page("## Setup", &plain).unwrap().body;
+        let expected: CallBody = parse_quote! {
+            h2 { id: "setup", "Setup" }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+    }
+    #[test]
+    fn test_heading_formatting(){
+        let content = "# **Bold** title\n\n## The `parse` function {#parse}\n\nSee [the *guide*](guide.md)\n---";
+        let page = parse_page(content, &ParseOptions::default()).unwrap();
+        let texts: Vec<&str> = page.headings.iter().map(|heading| heading.text.as_str()).collect();
+        assert_eq!(texts, ["Bold title", "The parse function", "See the guide"]);
+        let anchors: Vec<&str> = page.headings.iter().map(|heading| heading.anchor.as_str()).collect();
+        assert_eq!(anchors, ["bold-title", "parse", "see-the-guide"]);
+
+        let body = page.body;
+        let expected: CallBody = parse_quote! {
+            h1 {
+                id: "bold-title",
+                a { href: "#bold-title", class: "header", strong { "Bold" } " title" }
+            }
+            h2 {
+                id: "parse",
+                a { href: "#parse", class: "header", "The " code { "parse" } " function" }
+            }
+            h2 {
+                id: "see-the-guide",
+                a {
+                    href: "#see-the-guide",
+                    class: "header",
+                    "See "
+                    a { href: "guide", "the " em { "guide" } }
+                }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+    }
+    #[test]
+    fn test_footnotes(){
+        let content = "Text[^a] and[^b].\n\n[^b]: Bee.\n\n{{ div {} }}\n\nMore[^a].\n\n[^a]: Ay.";
+        let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+        let expected: CallBody = parse_quote! {
+            p {
+                "Text"
+                sup { class: "footnote-reference", id: "fnref-1", a { href: "#fn-1", "1" } }
+                " and"
+                sup { class: "footnote-reference", id: "fnref-2", a { href: "#fn-2", "2" } }
+                "."
+            }
+            div {}
+            p {
+                "More"
+                sup { class: "footnote-reference", id: "fnref-1-2", a { href: "#fn-1", "1" } }
+                "."
+            }
+            section {
+                class: "footnotes",
+                ol {
+                    li { id: "fn-1", p { "Ay." } a { href: "#fnref-1", class: "footnote-backref", "↩" } }
+                    li { id: "fn-2", p { "Bee." } a { href: "#fnref-2", class: "footnote-backref", "↩" } }
+                }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        // A definition written before its first reference doesn't take a number
+        let content = "[^x]: def first\n\nthen ref[^y] and[^x]\n\n[^y]: y";
+        let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+        let expected: CallBody = parse_quote! {
+            p {
+                "then ref"
+                sup { class: "footnote-reference", id: "fnref-1", a { href: "#fn-1", "1" } }
+                " and"
+                sup { class: "footnote-reference", id: "fnref-2", a { href: "#fn-2", "2" } }
+            }
+            section {
+                class: "footnotes",
+                ol {
+                    li { id: "fn-1", p { "y" } a { href: "#fnref-1", class: "footnote-backref", "↩" } }
+                    li { id: "fn-2", p { "def first" } a { href: "#fnref-2", class: "footnote-backref", "↩" } }
+                }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+    }
+    #[test]
+    fn test_raw_html(){
+        let content = "<details open>\n<summary>More &amp; more</summary>\n\nHidden **text**\n\n</details>\n\n\
+            Press <kbd>Ctrl</kbd> + <kbd>C</kbd><br>\n\n\
+            <iframe src=\"https://example.com\" allowfullscreen></iframe>\n\n\
+            <svg><circle r=\"1\"/></svg>";
+        let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+        let expected: CallBody = parse_quote! {
+            details {
+                "open": "",
+                summary { "More & more" }
+                p { "Hidden " strong { "text" } }
+            }
+            p {
+                "Press "
+                kbd { "Ctrl" }
+                " + "
+                kbd { "C" }
+                br {}
+            }
+            iframe { "src": "https://example.com", "allowfullscreen": "" }
+            p { span { dangerous_inner_html: "<svg><circle r=\"1\"/></svg>" } }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        // Invalid character references decode to U+FFFD, like in CommonMark
+        let body = parse_page("<abbr title=\"&#0;&#xD800;&#1114112;&#65;&#+1;\">A</abbr>", &ParseOptions::default()).unwrap().body;
+        let expected: CallBody = parse_quote! { p { abbr { "title": "\u{fffd}\u{fffd}\u{fffd}A&#+1;", "A" } } };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        let untrusted = ParseOptions { reject_html: true, ..Default::default() };
+        let errors = parse_page("# Title\n\nHello <b>you</b>", &untrusted).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[0].column), (3, 7));
+    }
+    #[test]
+    fn test_line_breaks(){
+        // Examples of the "Hard line breaks" and "Soft line breaks" sections of the CommonMark spec
+        let examples: [(&str, CallBody); 9] = [
+            ("foo  \nbaz", parse_quote!(p { "foo" br {} "baz" })),
+            ("foo\\\nbaz", parse_quote!(p { "foo" br {} "baz" })),
+            ("foo       \nbaz", parse_quote!(p { "foo" br {} "baz" })),
+            ("foo  \n     bar", parse_quote!(p { "foo" br {} "bar" })),
+            ("*foo  \nbar*", parse_quote!(p { em { "foo" br {} "bar" } })),
+            ("foo\\", parse_quote!(p { "foo\\" })),
+            ("### foo  ", parse_quote!(h3 { id: "foo", a { href: "#foo", class: "header", "foo" } })),
+            ("foo\nbaz", parse_quote!(p { "foo baz" })),
+            ("foo \n baz", parse_quote!(p { "foo baz" })),
+        ];
+        for (content, expected) in examples {
+            let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+            assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string(), "{content:?}");
+        }
+
+        let newlines = ParseOptions { newline_soft_breaks: true, ..Default::default() };
+        let body = parse_page("foo\nbaz", &newlines).unwrap().body;
+        let expected: CallBody = parse_quote!(p { "foo\nbaz" });
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        let body = parse_page("![two\nlines](a.png)", &ParseOptions::default()).unwrap().body;
+        assert!(quote!(#body).to_string().contains("\"two lines\""));
+    }
+    #[test]
+    fn test_task_list(){
+        let content = "- [x] done\n- [ ] todo";
+        let page = parse_page(content, &ParseOptions::default()).unwrap();
+        assert_eq!(page.tasks, [true, false]);
+        let body = page.body;
+        let expected: CallBody = parse_quote! {
+            ul {
+                li { input { r#type: "checkbox", checked: true, disabled: true, } "done" }
+                li { input { r#type: "checkbox", checked: false, disabled: true, } "todo" }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+
+        let interactive: ParseOptions = parse_quote!(on_toggle: on_toggle);
+        let page = parse_page(content, &interactive).unwrap();
+        let body = &page.body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains("__md_tasks . read () [1usize]"));
+        assert!(body.contains("__md_on_toggle . call"));
+        let state = task_state(&interactive.tasks, &page.tasks).to_string();
+        assert!(state.contains("use_signal (|| vec ! [true , false])"));
+        assert!(state.contains("= on_toggle ;"));
+    }
+    #[test]
+    fn test_tables(){
+        let content = "| Name | Count | Notes |\n|:-----|:-----:|------:|\n| a | 1 | x |\n| b | 2 | y |\n\n\
+            | Only |\n|---|\n\n| Last |\n|---|\n| z |";
+        let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+        let expected: CallBody = parse_quote! {
+            table {
+                thead {
+                    tr {
+                        th { style: "text-align: left", "Name" }
+                        th { style: "text-align: center", "Count" }
+                        th { style: "text-align: right", "Notes" }
+                    }
+                }
+                tbody {
+                    tr {
+                        td { style: "text-align: left", "a" }
+                        td { style: "text-align: center", "1" }
+                        td { style: "text-align: right", "x" }
+                    }
+                    tr {
+                        td { style: "text-align: left", "b" }
+                        td { style: "text-align: center", "2" }
+                        td { style: "text-align: right", "y" }
+                    }
+                }
+            }
+            table {
+                thead { tr { th { "Only" } } }
+            }
+            table {
+                thead { tr { th { "Last" } } }
+                tbody { tr { td { "z" } } }
+            }
+        };
+        assert_eq!(quote!(#body).to_string(), quote!(#expected).to_string());
+    }
+    #[test]
+    fn test_code_block_language(){
+        let syntaxes = syntect::parsing::SyntaxSet::load_defaults_newlines();
+        let name = |info: &str| highlight::find_syntax(&syntaxes, fence_info(info).unwrap().lang.as_deref()).1.name.clone();
+        assert_eq!(name("rust,ignore"), "Rust");
+        assert_eq!(name("Python"), "Python");
+        assert_eq!(name("sh"), "Bourne Again Shell (bash)");
+        assert_eq!(name("console"), "Bourne Again Shell (bash)");
+        assert_eq!(name("tsx"), "JavaScript");
+        assert_eq!(name("json"), "JSON");
+        assert_eq!(name("toml"), "TOML");
+        assert_eq!(name("brainfuck"), "Plain Text");
+        assert_eq!(name(""), "Plain Text");
+
+        let content = "```toml title=\"Cargo.toml\"\n[package]\n```\n\n```\nplain\n```";
+        let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+        let data_lang = |node: &BodyNode| {
+            let BodyNode::Element(element) = node else { panic!("not an element") };
+            element.merged_attributes.iter().find_map(|attribute| {
+                let (dioxus_rsx::AttributeName::Custom(name), value) = attribute.as_static_str_literal()? else {
+                    return None;
+                };
+                (name.value() == "data-lang").then(|| value.to_static().unwrap())
+            })
+        };
+        assert_eq!(data_lang(&body.body.roots[0]).as_deref(), Some("toml"));
+        assert_eq!(data_lang(&body.body.roots[1]), None);
+
+        // TOML has its own syntax
+        let options: ParseOptions = parse_str("highlight_classes: true").unwrap();
+        let content = "```toml\n[dependencies]\ndioxus = { version = \"0.6\", features = [\"web\"] } # ui\n```";
+        let body = parse_page(content, &options).unwrap().body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains("hl-entity hl-name hl-section hl-toml"));
+        assert!(body.contains("hl-entity hl-name hl-tag hl-toml"));
+        assert!(body.contains("hl-string hl-quoted hl-double hl-toml"));
+        assert!(body.contains("hl-comment hl-line hl-number-sign hl-toml"));
+    }
+    #[test]
+    fn test_highlight_classes(){
+        let options: ParseOptions = parse_str("highlight_classes: true").unwrap();
+        let body = parse_page("```rust\nfn main() {}\n```", &options).unwrap().body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains(r#"<pre class=\"hl-code\"><span class=\"hl-source hl-rust\">"#));
+        assert!(body.contains(r#"<span class=\"hl-storage hl-type hl-function hl-rust\">fn</span>"#));
+        assert!(!body.contains("style=\\\"color"));
+
+        let themes = HighlightThemes {
+            default: "InspiredGitHub".to_string(),
+            dark: Some("base16-ocean.dark".to_string()),
+            ..Default::default()
+        };
+        let css = highlight_css(&themes, None).unwrap();
+        assert!(css.starts_with(".hl-line {\n display: flex;\n}\n"));
+        assert!(css.contains("}\n\n/*\n * theme \"GitHub\""));
+        assert!(css.contains("\n@media (prefers-color-scheme: dark) {\n/*\n * theme \"Base16 Ocean Dark\""));
+        assert!(!css.contains("prefers-color-scheme: light"));
+        let unknown = HighlightThemes { light: Some("Dracula".to_string()), ..themes };
+        assert!(highlight_css(&unknown, None).unwrap_err().to_string().starts_with("unknown theme `Dracula`"));
+
+        assert_eq!(highlight::asset_dir("[application]\nname = \"docs\"\nasset_dir = \"public\"\n"), "public");
+        assert_eq!(highlight::asset_dir(""), "assets");
+    }
+    #[test]
+    fn test_custom_highlighting(){
+        let info = fence_info("rust,ignore theme=\"Solarized (light)\" linenos").unwrap();
+        assert_eq!((info.lang.as_deref(), info.theme.as_deref()), (Some("rust"), Some("Solarized (light)")));
+
+        let dir = std::env::temp_dir().join(format!("parse-markdown-highlight-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("turtle.sublime-syntax"), "%YAML 1.2\n---\nname: Turtle\nfile_extensions: [turtle]\nscope: source.turtle\n\
+            contexts:\n  main:\n    - match: '\\b(forward|left)\\b'\n      scope: keyword.control.turtle\n").unwrap();
+        let theme = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <plist version="1.0"><dict>
+            <key>name</key><string>Brand</string>
+            <key>settings</key><array>
+            <dict><key>settings</key><dict><key>background</key><string>#102030</string><key>foreground</key><string>#FFFFFF</string></dict></dict>
+            <dict><key>scope</key><string>keyword</string><key>settings</key><dict><key>foreground</key><string>#FF8800</string></dict></dict>
+            </array></dict></plist>"#;
+        std::fs::write(dir.join("brand.tmTheme"), theme).unwrap();
+
+        let mut options: ParseOptions = parse_str("highlight_theme: \"brand\"").unwrap();
+        options.highlight.dir = Some(dir.clone());
+        let body = parse_page("```turtle\nforward 10\n```", &options).unwrap().body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains(r#"<pre style=\"background-color:#102030;\">"#));
+        assert!(body.contains(r#"<span style=\"color:#ff8800;\">forward</span>"#));
+
+        // A block choosing its theme is highlighted with inline styles, even with classes
+        options.highlight.classes = true;
+        let body = parse_page("```turtle theme=brand\nleft\n```\n\n```turtle\nleft\n```", &options).unwrap().body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains(r#"<span style=\"color:#ff8800;\">left\n</span>"#));
+        assert!(body.contains(r#"<span class=\"hl-keyword hl-control hl-turtle\">left</span>"#));
+
+        let errors = parse_page("```rust theme=Dracula\n```", &options).unwrap_err();
+        assert!(errors[0].message.starts_with("unknown theme `Dracula`, expected one of"));
+        assert_eq!(errors[0].line, 1);
+
+        // Added and edited files are loaded again, and loading errors are not kept
+        std::fs::write(dir.join("broken.sublime-syntax"), "name: [").unwrap();
+        assert!(parse_page("```turtle\nleft\n```", &options).is_err());
+        std::fs::remove_file(dir.join("broken.sublime-syntax")).unwrap();
+        std::fs::write(dir.join("night.tmTheme"), theme).unwrap();
+        assert!(parse_page("```turtle theme=night\nleft\n```", &options).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn test_highlight_dump(){
+        let dir = std::env::temp_dir().join(format!("parse-markdown-dump-{}", std::process::id()));
+        let dump_dir = dir.join("out");
+        std::fs::create_dir_all(&dump_dir).unwrap();
+        std::fs::write(dir.join("turtle.sublime-syntax"), "%YAML 1.2\n---\nname: Turtle\nfile_extensions: [turtle]\n\
+            scope: source.turtle\ncontexts:\n  main:\n    - match: forward\n      scope: keyword.turtle\n").unwrap();
+        highlight::write_dump(Some(&dir), &dump_dir).unwrap();
+
+        // The syntaxes are only found in the dump: the directory of the project doesn't exist
+        let mut options: ParseOptions = parse_str("highlight_classes: true").unwrap();
+        options.highlight.dir = Some(dir.join("missing"));
+        options.highlight.dump_dir = Some(dump_dir);
+        for _ in 0..2 {
+            let body = parse_page("```turtle\nforward\n```", &options).unwrap().body;
+            assert!(quote!(#body).to_string().contains(r#"<span class=\"hl-keyword hl-turtle\">forward</span>"#));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn test_code_block_attributes(){
+        let info = fence_info("rust title=\"main.rs\" linenos hl_lines=\"1 3-5\"").unwrap();
+        assert_eq!(info.title.as_deref(), Some("main.rs"));
+        assert!(info.linenos);
+        assert_eq!(info.hl_lines, vec![(1, 1), (3, 5)]);
+        assert_eq!(fence_info("hl_lines=2,4").unwrap().hl_lines, vec![(2, 2), (4, 4)]);
+        assert_eq!(fence_info("title=\"a b.rs\"").unwrap().lang, None);
+        assert!(fence_info("rust hl_lines=5-3").unwrap_err().starts_with("invalid line range `5-3` in hl_lines"));
+
+        let content = "```rust title=\"main.rs\" linenos hl_lines=\"2\"\nfn main() {\n    run();\n}\n```";
+        let body = parse_page(content, &ParseOptions::default()).unwrap().body;
+        let BodyNode::Element(block) = &body.body.roots[0] else { panic!("not an element") };
+        let title = &block.children[0];
+        let expected: BodyNode = parse_quote!(div { class: "hl-title", "main.rs" });
+        assert_eq!(quote!(#title).to_string(), quote!(#expected).to_string());
+        let fence = fence_info("rust linenos hl_lines=\"2\"").unwrap();
+        let html = highlight(&HighlightOptions::default(), "fn main() {\n    run();\n}\n", &fence).unwrap();
+        assert_eq!(html.matches(r#"<span class="hl-lineno""#).count(), 3);
+        assert!(html.contains(r#"<span class="hl-line hl-highlighted" style="display: flex; background-color: #65737e30;"><span class="hl-lineno" style="user-select: none; margin-right: 1em; opacity: 0.6;">2</span>"#));
+
+        let options: ParseOptions = parse_str("highlight_classes: true").unwrap();
+        let body = parse_page("```text hl_lines=2\na\nb\n```", &options).unwrap().body;
+        let body = quote!(#body).to_string();
+        assert!(body.contains(r#"<pre class=\"hl-code\"><span class=\"hl-line\"><span><span class=\"hl-text hl-plain\">a\n</span></span></span><span class=\"hl-line hl-highlighted\"><span>"#));
+        assert!(highlight_css(&HighlightThemes::default(), None).unwrap().contains(".hl-highlighted {\n background-color: #65737e30;\n}"));
+
+        let errors = parse_page("```rust hl_lines=x\n```", &ParseOptions::default()).unwrap_err();
+        assert_eq!(errors[0].message, "invalid line range `x` in hl_lines, expected a line like `3` or lines like `3-5`");
+    }
+    #[cfg(feature = "hot-reload")]
+    #[test]
+    fn test_hot_reload_diff(){
+        use hot_reload::{diff_pages, find_page_calls, PageChange};
+
+        let calls = find_page_calls("src/main.rs", "fn Home() -> Element {\n    md_page!(\"src/pages/hello.md\")\n}");
+        assert_eq!(calls.len(), 1);
+        let (page, location, options) = (&calls[0].page, &calls[0].location, &calls[0].options);
+        assert_eq!(page, "src/pages/hello.md");
+        assert_eq!((location.line, location.column), (2, 5));
+
+        let page = "# Title\n\nHello {=name}, {{ Greet { name: \"x\" } }}\n";
+        let PageChange::Template(template) = diff_pages(page, &page.replace("Hello", "Bye"), options, location) else {
+            panic!("static text should be hot reloaded");
+        };
+        assert_eq!(template.key.line, 2);
+        assert!(matches!(diff_pages(page, page, options, location), PageChange::Unchanged));
+        assert!(matches!(diff_pages(page, &page.replace("Greet", "Other"), options, location), PageChange::Rebuild));
+        assert!(matches!(diff_pages(page, &page.replace("{=name}", "{=age}"), options, location), PageChange::Rebuild));
+
+        // The page is diffed with the options of the call: the link is a dynamic `Link` component
+        let source = "fn Home() -> Element {\n    rsx! { {md_page!(\"src/pages/hello.md\", routes: { \"/clear\" => Route::Clear {} })} }\n}";
+        let calls = find_page_calls("src/main.rs", source);
+        assert_eq!(calls.len(), 1);
+        let (location, options) = (&calls[0].location, &calls[0].options);
+        assert_eq!((location.line, location.column), (2, 13));
+        let page = "Go to [clear](/clear).\n";
+        let PageChange::Template(template) = diff_pages(page, &page.replace("Go to", "See"), options, location) else {
+            panic!("static text should be hot reloaded");
+        };
+        assert_eq!(template.template.dynamic_nodes.len(), 1);
+        assert!(matches!(diff_pages(page, &page.replace("[clear]", "[reset]"), options, location), PageChange::Rebuild));
+    }
+    #[test]
+    fn test_collect_all_errors(){
+        let content = "{{ div { , } }}\n\nok\n\n```inject-dioxus\ndiv {\n```\n\n{{ \"fine\" }}";
+        let errors = parse_page(content, &ParseOptions::default()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[1].line), (1, 6));
+
+        let lenient = ParseOptions { lenient: true, ..Default::default() };
+        let page = parse_page(content, &lenient).unwrap();
+        assert_eq!(page.diagnostics, errors);
+        // placeholder, paragraph, placeholder, text
+        assert_eq!(page.body.body.roots.len(), 4);
     }
 }
@@ -0,0 +1,149 @@
+//! Where the links of a page lead.
+//!
+//! Links to other markdown pages (`other.md`, `../guide/index.md`) become paths of the app
+//! (`other`, `../guide`). With the router, they are rendered as `Link` so that following them
+//! doesn't reload the whole app.
+
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    Expr, LitStr, Token,
+};
+
+/// How links are rendered.
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    /// Render links inside the app with the `Link` component of dioxus-router
+    pub router: bool,
+    /// The path of the page in the app, used to resolve relative links for the router.
+    /// Defaults to the root: `/`
+    pub base: String,
+    /// The route to link to for some paths, like `"/perfect_clear" => Route::PerfectClearPage {}`
+    pub routes: HashMap<String, TokenStream>,
+    /// The `rel` attribute of links to other sites, like `noopener noreferrer`
+    pub external_rel: Option<String>,
+    /// Open links to other sites in a new tab
+    pub external_new_tab: bool,
+    /// Fail on links to markdown pages or headings that don't exist (see [`PageIndex`](crate::PageIndex))
+    pub check: bool,
+}
+
+/// The `routes: { "/path" => Route::Variant {}, ... }` option.
+pub(crate) struct Routes(pub(crate) HashMap<String, TokenStream>);
+
+impl Parse for Routes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        braced!(content in input);
+        let mut routes = HashMap::new();
+        while !content.is_empty() {
+            let path: LitStr = content.parse()?;
+            content.parse::<Token![=>]>()?;
+            let route: Expr = content.parse()?;
+            routes.insert(path.value(), quote::quote!(#route));
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+        Ok(Self(routes))
+    }
+}
+
+/// Where a link of the page leads.
+#[derive(Debug)]
+pub(crate) enum LinkTarget {
+    /// A page of the site, or a place in the current page, linked with a plain anchor
+    Anchor(String),
+    /// A page of the app, linked with the router
+    Router(String),
+    /// A known route of the app
+    Route(TokenStream),
+    /// Another site, or a `mailto:` address
+    External(String),
+}
+
+impl LinkOptions {
+    pub(crate) fn target(&self, dest: &str, email: bool) -> LinkTarget {
+        if email {
+            return LinkTarget::External(format!("mailto:{dest}"));
+        }
+        if is_external(dest) {
+            return LinkTarget::External(dest.to_string());
+        }
+
+        let (file, suffix) = split_suffix(dest);
+        let mut path = page_path(file);
+        // The root `index.md` is the directory of the base, not an empty link to the current page
+        if path.is_empty() && !file.is_empty() {
+            path = resolve(&self.base, "");
+        }
+        if !self.router || dest.starts_with('#') {
+            return LinkTarget::Anchor(format!("{path}{suffix}"));
+        }
+
+        let path = resolve(&self.base, &path);
+        match self.routes.get(&path) {
+            Some(route) if suffix.is_empty() => LinkTarget::Route(route.clone()),
+            _ => LinkTarget::Router(format!("{path}{suffix}")),
+        }
+    }
+}
+
+/// A link with a scheme (`https:`, `mailto:`) or a protocol-relative link (`//host/path`)
+pub(crate) fn is_external(dest: &str) -> bool {
+    if dest.starts_with("//") {
+        return true;
+    }
+    dest.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Split `path?query#fragment` into the path and the rest
+pub(crate) fn split_suffix(dest: &str) -> (&str, &str) {
+    let end = dest.find(['?', '#']).unwrap_or(dest.len());
+    dest.split_at(end)
+}
+
+/// The path of the app for a markdown file: without the `.md` extension and the `/index` page.
+fn page_path(path: &str) -> String {
+    let without_extension = path.strip_suffix(".md").unwrap_or(path);
+    if without_extension == "index" {
+        return String::new();
+    }
+    without_extension
+        .strip_suffix("/index")
+        .unwrap_or(without_extension)
+        .to_string()
+}
+
+/// Resolve a path relative to the directory of `base`, like a browser would.
+fn resolve(base: &str, path: &str) -> String {
+    let base = if base.is_empty() { "/" } else { base };
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        // the directory of the page
+        let dir = &base[..base.rfind('/').map_or(0, |i| i + 1)];
+        format!("{dir}{path}")
+    };
+
+    let mut segments: Vec<&str> = vec![];
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
@@ -0,0 +1,112 @@
+use proc_macro2::TokenStream;
+use syn::{
+    parse::{Parse, ParseStream},
+    Expr, Ident, LitBool, LitStr, Token,
+};
+
+use crate::components::{parse_component_call, ComponentMap, MarkdownElement};
+use crate::highlight::HighlightOptions;
+use crate::links::{LinkOptions, Routes};
+use crate::tasks::TaskOptions;
+
+/// Options controlling how a page is parsed.
+///
+/// They can also be parsed from the arguments of the macros:
+///
+/// ```text
+/// link => Link { to: {href} },
+/// router: true,
+/// routes: { "/perfect_clear" => Route::PerfectClearPage {} },
+/// external_rel: "noopener noreferrer",
+/// check_links: true,
+/// plain_headings: true,
+/// on_toggle: on_toggle,
+/// highlight_classes: true,
+/// highlight_theme: "InspiredGitHub",
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Don't fail on broken `{{ }}` blocks or code fences: replace each of them
+    /// with a placeholder node and report the errors in [`Page::diagnostics`](crate::Page::diagnostics).
+    pub lenient: bool,
+    /// Components rendering markdown elements instead of the default html
+    pub components: ComponentMap,
+    pub links: LinkOptions,
+    pub tasks: TaskOptions,
+    pub highlight: HighlightOptions,
+    /// Render headings without the `a.header` link to themselves
+    pub plain_headings: bool,
+    /// Fail on raw HTML, for pages written by untrusted users
+    pub reject_html: bool,
+    /// Render the line breaks inside paragraphs as `\n` instead of a space,
+    /// for text styled with `white-space: pre-line`
+    pub newline_soft_breaks: bool,
+}
+
+impl ParseOptions {
+    /// Render `element` with `component`, a component call like `Link { to: {href} }`.
+    ///
+    /// Each `{attribute}` in the call is replaced by the value of this attribute of the element
+    /// (see [`MarkdownElement`]), and the content of the element becomes the children of the component.
+    pub fn with_component(mut self, element: MarkdownElement, component: TokenStream) -> syn::Result<Self> {
+        self.components.insert(element, component)?;
+        Ok(self)
+    }
+}
+
+impl Parse for ParseOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = ParseOptions::default();
+        while !input.is_empty() {
+            if input.peek2(Token![=>]) {
+                let element: MarkdownElement = input.parse()?;
+                input.parse::<Token![=>]>()?;
+                options.components.insert(element, parse_component_call(input)?)?;
+            } else {
+                let key: Ident = input.parse()?;
+                input.parse::<Token![:]>()?;
+                match key.to_string().as_str() {
+                    "lenient" => options.lenient = input.parse::<LitBool>()?.value,
+                    "plain_headings" => options.plain_headings = input.parse::<LitBool>()?.value,
+                    "reject_html" => options.reject_html = input.parse::<LitBool>()?.value,
+                    "newline_soft_breaks" => {
+                        options.newline_soft_breaks = input.parse::<LitBool>()?.value
+                    }
+                    "router" => options.links.router = input.parse::<LitBool>()?.value,
+                    "base" => options.links.base = input.parse::<LitStr>()?.value(),
+                    "routes" => {
+                        options.links.routes = input.parse::<Routes>()?.0;
+                        options.links.router = true;
+                    }
+                    "external_rel" => {
+                        options.links.external_rel = Some(input.parse::<LitStr>()?.value())
+                    }
+                    "external_new_tab" => {
+                        options.links.external_new_tab = input.parse::<LitBool>()?.value
+                    }
+                    "check_links" => options.links.check = input.parse::<LitBool>()?.value,
+                    "interactive_tasks" => options.tasks.interactive = input.parse::<LitBool>()?.value,
+                    "on_toggle" => {
+                        let on_toggle: Expr = input.parse()?;
+                        options.tasks.on_toggle = Some(quote::quote!(#on_toggle));
+                        options.tasks.interactive = true;
+                    }
+                    "highlight_classes" => options.highlight.classes = input.parse::<LitBool>()?.value,
+                    "highlight_theme" => options.highlight.theme = Some(input.parse::<LitStr>()?.value()),
+                    _ => {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            format!("unknown option `{key}`, expected a markdown element followed by `=>`, or one of lenient, plain_headings, reject_html, newline_soft_breaks, router, base, routes, external_rel, external_new_tab, check_links, interactive_tasks, on_toggle, highlight_classes, highlight_theme"),
+                        ))
+                    }
+                }
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,251 @@
+//! Find the `{{ }}` rsx blocks of a page.
+//!
+//! A regex can't do this: the rust code inside a block can itself contain `}}`
+//! (tight closing braces, format strings, comments), and `{{` written inside
+//! markdown code is documentation, not a block. So we scan the page by hand,
+//! skipping markdown code and balancing braces inside rust code.
+//!
+//! All the delimiters we look for are ASCII, so scanning bytes is safe in UTF-8 text.
+
+use std::ops::Range;
+
+/// A `{{ }}` block found in the page.
+#[derive(Debug, PartialEq)]
+pub(crate) enum RsxBlockRange {
+    /// Byte range of the whole block, braces included
+    Closed(Range<usize>),
+    /// A `{{` without its `}}`, starting at this byte offset
+    Unclosed(usize),
+}
+
+pub(crate) fn find_rsx_blocks(text: &str) -> Vec<RsxBlockRange> {
+    let bytes = text.as_bytes();
+    let mut blocks = vec![];
+    let mut fence: Option<(u8, usize)> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let line_start = i == 0 || bytes[i - 1] == b'\n';
+
+        if line_start {
+            if let Some((c, len)) = code_fence(&text[i..]) {
+                match fence {
+                    None => fence = Some((c, len)),
+                    Some((open_c, open_len)) if open_c == c && len >= open_len => fence = None,
+                    Some(_) => {}
+                }
+                i = line_end(text, i);
+                continue;
+            }
+            if fence.is_some() {
+                i = line_end(text, i);
+                continue;
+            }
+        }
+
+        match bytes[i] {
+            // `\{{` is a literal `{{`, and markdown will remove the backslash
+            b'\\' => i += 2,
+            b'`' => {
+                let run = run_length(bytes, i, b'`');
+                i += run;
+                if let Some(end) = find_backtick_run(bytes, i, run) {
+                    i = end + run;
+                }
+            }
+            b'{' if bytes.get(i + 1) == Some(&b'{') => match rsx_block_end(bytes, i + 2) {
+                Some(end) => {
+                    blocks.push(RsxBlockRange::Closed(i..end));
+                    i = end;
+                }
+                None => {
+                    blocks.push(RsxBlockRange::Unclosed(i));
+                    break;
+                }
+            },
+            _ => i += 1,
+        }
+    }
+    blocks
+}
+
+/// If the line opens or closes a fenced code block, the fence character and length.
+/// A fence is indented by 3 spaces at most: with more, the line is indented code.
+fn code_fence(line: &str) -> Option<(u8, usize)> {
+    let indent = run_length(line.as_bytes(), 0, b' ');
+    if indent > 3 {
+        return None;
+    }
+    let line = &line.as_bytes()[indent..];
+    let c = *line.first()?;
+    if c != b'`' && c != b'~' {
+        return None;
+    }
+    let len = run_length(line, 0, c);
+    (len >= 3).then_some((c, len))
+}
+
+fn line_end(text: &str, from: usize) -> usize {
+    text[from..].find('\n').map_or(text.len(), |i| from + i + 1)
+}
+
+fn run_length(bytes: &[u8], from: usize, c: u8) -> usize {
+    bytes[from..].iter().take_while(|b| **b == c).count()
+}
+
+/// Find the next run of exactly `len` backticks, which closes an inline code span.
+/// A code span can't go past the end of its paragraph, at a blank line.
+fn find_backtick_run(bytes: &[u8], mut from: usize, len: usize) -> Option<usize> {
+    while from < bytes.len() {
+        match bytes[from] {
+            b'`' => {
+                let run = run_length(bytes, from, b'`');
+                if run == len {
+                    return Some(from);
+                }
+                from += run;
+            }
+            b'\n' if is_blank_line(&bytes[from + 1..]) => return None,
+            _ => from += 1,
+        }
+    }
+    None
+}
+
+/// Whether the line starting at `bytes` only has whitespace
+fn is_blank_line(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .take_while(|b| **b != b'\n')
+        .all(|b| matches!(b, b' ' | b'\t' | b'\r'))
+}
+
+/// Scan rust code starting at `i` until the `}}` closing the block.
+/// Returns the offset just after the closing braces.
+fn rsx_block_end(bytes: &[u8], i: usize) -> Option<usize> {
+    rust_code_end(bytes, i, b"}}")
+}
+
+/// Scan the rust expression of a `{=expr}` interpolation, starting at `i`, until its closing `}`.
+/// Returns the offset just after the closing brace.
+pub(crate) fn expression_end(text: &str, i: usize) -> Option<usize> {
+    rust_code_end(text.as_bytes(), i, b"}")
+}
+
+/// Scan rust code starting at `i` until `closing`, outside of any braces, strings and comments.
+/// Returns the offset just after `closing`.
+fn rust_code_end(bytes: &[u8], mut i: usize, closing: &[u8]) -> Option<usize> {
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' if depth == 0 && bytes[i..].starts_with(closing) => return Some(i + closing.len()),
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            b'"' => i = string_end(bytes, i + 1)?,
+            b'r' | b'b' if !is_ident_byte(i.checked_sub(1).map(|j| bytes[j])) => {
+                i = raw_string_end(bytes, i).unwrap_or(i + 1);
+            }
+            b'\'' => i = char_end(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map_or(bytes.len(), |p| i + p);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = block_comment_end(bytes, i + 2)?,
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn is_ident_byte(b: Option<u8>) -> bool {
+    b.is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_' || !b.is_ascii())
+}
+
+/// End of a `"..."` string whose content starts at `i`.
+fn string_end(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// End of a raw string (`r"..."`, `r#"..."#`, `br"..."`) starting at `i`,
+/// or `None` if this is just an identifier starting with `r` or `b`.
+fn raw_string_end(bytes: &[u8], mut i: usize) -> Option<usize> {
+    if bytes[i] == b'b' {
+        i += 1;
+        if bytes.get(i) == Some(&b'"') {
+            return string_end(bytes, i + 1);
+        }
+    }
+    if bytes.get(i) != Some(&b'r') {
+        return None;
+    }
+    let hashes = run_length(bytes, i + 1, b'#');
+    i += 1 + hashes;
+    if bytes.get(i) != Some(&b'"') {
+        return None;
+    }
+    i += 1;
+    while i < bytes.len() {
+        if bytes[i] == b'"' && run_length(bytes, i + 1, b'#') >= hashes {
+            return Some(i + 1 + hashes);
+        }
+        i += 1;
+    }
+    Some(bytes.len())
+}
+
+/// Skip a char literal (`'a'`, `'\''`, `'{'`) or the quote of a lifetime starting at `i`.
+fn char_end(bytes: &[u8], i: usize) -> usize {
+    if bytes.get(i + 1) == Some(&b'\\') {
+        // escaped char literal: find the closing quote
+        return bytes
+            .get(i + 3..)
+            .and_then(|rest| rest.iter().position(|b| *b == b'\''))
+            .map_or(bytes.len(), |p| i + 3 + p + 1);
+    }
+    let char_len = match bytes.get(i + 1) {
+        Some(b) if b.is_ascii() => 1,
+        Some(b) => b.leading_ones() as usize,
+        None => return i + 1,
+    };
+    if bytes.get(i + 1 + char_len) == Some(&b'\'') {
+        i + char_len + 2
+    } else {
+        // a lifetime
+        i + 1
+    }
+}
+
+/// End of a (possibly nested) `/* */` comment whose content starts at `i`.
+fn block_comment_end(bytes: &[u8], mut i: usize) -> Option<usize> {
+    let mut depth = 1;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"/*") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
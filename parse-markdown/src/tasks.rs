@@ -0,0 +1,72 @@
+//! The checkboxes of task lists: `- [x] done` and `- [ ] todo`.
+//!
+//! By default they show the state written in the markdown and can't be changed.
+//! In interactive mode, their state is kept in a signal of the component rendering the page,
+//! and an `EventHandler<TaskToggle>` of the component is called when one of them is clicked.
+
+use dioxus_rsx::BodyNode;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// How the checkboxes of task lists behave.
+#[derive(Debug, Clone, Default)]
+pub struct TaskOptions {
+    /// Let the reader check and uncheck the tasks
+    pub interactive: bool,
+    /// An `EventHandler<dioxus_markdown::TaskToggle>`, called when a task is checked or unchecked
+    pub on_toggle: Option<TokenStream>,
+    /// The name of the page in the `TaskToggle` events, the path of the page for `md_page!`
+    pub page: String,
+}
+
+/// The checkbox of the task number `index` of the page
+pub(crate) fn checkbox(options: &TaskOptions, index: usize, checked: bool) -> BodyNode {
+    if !options.interactive {
+        return parse_quote! {
+            input {
+                r#type: "checkbox",
+                checked: #checked,
+                disabled: true,
+            }
+        };
+    }
+    let page = &options.page;
+    let on_toggle = options.on_toggle.as_ref().map(|_| {
+        quote! {
+            __md_on_toggle.call(::dioxus_markdown::TaskToggle {
+                page: #page,
+                index: #index,
+                checked,
+            });
+        }
+    });
+    parse_quote! {
+        input {
+            r#type: "checkbox",
+            checked: __md_tasks.read()[#index],
+            onchange: move |event: ::dioxus::prelude::FormEvent| {
+                let checked = event.checked();
+                __md_tasks.write()[#index] = checked;
+                #on_toggle
+            },
+        }
+    }
+}
+
+/// The statements the body of an interactive page needs before it: the signal with the state of
+/// each task, starting with the state written in the markdown, and the handler called on changes.
+pub fn task_state(options: &TaskOptions, tasks: &[bool]) -> TokenStream {
+    if !options.interactive || tasks.is_empty() {
+        return quote!();
+    }
+    let on_toggle = options.on_toggle.as_ref().map(|on_toggle| {
+        quote! {
+            let __md_on_toggle: ::dioxus::prelude::EventHandler<::dioxus_markdown::TaskToggle> = #on_toggle;
+        }
+    });
+    quote! {
+        let mut __md_tasks = ::dioxus::prelude::use_signal(|| vec![#(#tasks),*]);
+        #on_toggle
+    }
+}
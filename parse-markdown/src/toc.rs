@@ -0,0 +1,127 @@
+//! The headings of a page: their anchors, and the table of contents built from them.
+//!
+//! Anchors are slugs of the text of the headings, like GitHub makes them: `## Setup` has the id `setup`,
+//! and a second `## Setup` in the same page has the id `setup-1`.
+//!
+//! A paragraph containing only `[[toc]]` is replaced by a nested list of links to the headings of the page.
+
+use dioxus_rsx::BodyNode;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+use crate::{escape_text, interpolate_text, PageHeading};
+
+/// The marker replaced by the table of contents
+pub(crate) const TOC_MARKER: &str = "[[toc]]";
+
+/// The slug of the text of a heading: lowercase, without punctuation, with `-` in place of spaces.
+/// Letters and digits of every language are kept.
+pub(crate) fn slug(text: &str) -> String {
+    let mut slug = String::new();
+    for char in text.trim().chars() {
+        match char {
+            ' ' => slug.push('-'),
+            '-' | '_' => slug.push(char),
+            char if char.is_alphanumeric() => slug.extend(char.to_lowercase()),
+            _ => {}
+        }
+    }
+    slug
+}
+
+/// Split the `{#id .class}` at the end of the text of a heading.
+/// Any other braces, like a `{=expr}` interpolation, are part of the text.
+pub(crate) fn heading_attributes(text: &str) -> (&str, Option<&str>, Vec<&str>) {
+    let trimmed = text.trim_end();
+    let Some((head, attributes)) = trimmed
+        .strip_suffix('}')
+        .and_then(|rest| rest.rsplit_once('{'))
+    else {
+        return (text, None, vec![]);
+    };
+    let attributes: Vec<&str> = attributes.split_whitespace().collect();
+    let is_attribute = |attribute: &&str| {
+        attribute.len() > 1 && (attribute.starts_with('#') || attribute.starts_with('.'))
+    };
+    if attributes.is_empty() || !attributes.iter().all(is_attribute) {
+        return (text, None, vec![]);
+    }
+    let id = attributes.iter().rev().find_map(|attribute| attribute.strip_prefix('#'));
+    let classes = attributes.iter().filter_map(|attribute| attribute.strip_prefix('.')).collect();
+    (head.trim_end(), id, classes)
+}
+
+/// Make `slug` unique among the anchors of `headings`, by adding `-1`, `-2`, ...
+pub(crate) fn unique_slug(slug: &str, headings: &[PageHeading]) -> String {
+    let is_used = |anchor: &str| headings.iter().any(|heading| heading.anchor == anchor);
+    if !is_used(slug) {
+        return slug.to_string();
+    }
+    (1..)
+        .map(|count| format!("{slug}-{count}"))
+        .find(|anchor| !is_used(anchor))
+        .unwrap()
+}
+
+/// A heading, with the headings of the section it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocNode {
+    pub heading: PageHeading,
+    pub children: Vec<TocNode>,
+}
+
+/// Nest the headings of a page: each heading contains the following headings of a deeper level.
+pub fn toc_tree(headings: &[PageHeading]) -> Vec<TocNode> {
+    nest(&mut headings.iter().peekable(), 0)
+}
+
+fn nest<'a>(headings: &mut std::iter::Peekable<impl Iterator<Item = &'a PageHeading>>, level: u8) -> Vec<TocNode> {
+    let mut nodes = vec![];
+    while let Some(heading) = headings.next_if(|heading| heading.level > level) {
+        let children = nest(headings, heading.level);
+        nodes.push(TocNode {
+            heading: heading.clone(),
+            children,
+        });
+    }
+    nodes
+}
+
+/// The `ul` of links rendering a table of contents
+pub(crate) fn toc_list(nodes: &[TocNode]) -> BodyNode {
+    let items = toc_items(nodes);
+    parse_quote! {
+        ul {
+            class: "toc",
+            #items
+        }
+    }
+}
+
+fn toc_items(nodes: &[TocNode]) -> TokenStream {
+    let items = nodes.iter().map(|node| {
+        let href = escape_text(&format!("#{}", node.heading.anchor));
+        let text = toc_text(&node.heading.text);
+        let children = (!node.children.is_empty()).then(|| {
+            let children = toc_items(&node.children);
+            quote!(ul { #children })
+        });
+        quote! {
+            li {
+                a { href: #href, #text }
+                #children
+            }
+        }
+    });
+    quote!(#(#items)*)
+}
+
+/// The text of a heading. If its `{=expr}` interpolations are invalid,
+/// the error was already reported at the heading: keep the text literal.
+fn toc_text(text: &str) -> TokenStream {
+    interpolate_text(text).unwrap_or_else(|_| {
+        let text = escape_text(text);
+        quote!(#text)
+    })
+}
@@ -2,6 +2,7 @@
 
 use dioxus::prelude::*;
 use dioxus_logger::tracing::{info, Level};
+use dioxus_markdown::{md_meta, md_page, PageMeta};
 
 mod perfect_clear;
 use perfect_clear::PerfectClear;
@@ -21,8 +22,12 @@ fn main() {
     launch(App);
 }
 
+pub const HELLO: PageMeta = md_meta!("src/pages/hello.md");
+
 fn App() -> Element {
+    let title = HELLO.title.unwrap_or("Dioxus markdown");
     rsx! {
+        document::Title { "{title}" }
         Router::<Route> {}
     }
 }
@@ -48,5 +53,5 @@ fn Greet(name: String) -> Element {
 
 #[component]
 fn Home() -> Element {
-    include!("pages/hello.rs")
+    md_page!("src/pages/hello.md", routes: { "/perfect_clear" => Route::PerfectClearPage {} })
 }